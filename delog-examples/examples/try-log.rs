@@ -10,7 +10,7 @@ delog!(Delogger, 196, StdoutFlusher, renderer: RipgrepRenderer);
 generate_macros!();
 
 static STDOUT_FLUSHER: StdoutFlusher = StdoutFlusher {};
-static RENDERER: RipgrepRenderer = RipgrepRenderer {};
+static RENDERER: RipgrepRenderer = RipgrepRenderer::new();
 
 fn main() {
     Delogger::init(delog::LevelFilter::Info, &STDOUT_FLUSHER, &RENDERER).ok();
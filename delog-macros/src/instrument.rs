@@ -0,0 +1,229 @@
+//! The `#[instrument]` attribute.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{meta::ParseNestedMeta, spanned::Spanned, visit::Visit, Error, Ident, Result};
+
+pub struct Options {
+    level: Ident,
+    skip: Vec<Ident>,
+    skip_return: bool,
+    hex: Vec<Ident>,
+    elapsed: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            level: Ident::new("trace", Span::call_site()),
+            skip: Vec::new(),
+            skip_return: false,
+            hex: Vec::new(),
+            elapsed: false,
+        }
+    }
+}
+
+const LEVELS: [(&str, &str); 5] = [
+    ("error", "Error"),
+    ("warn", "Warn"),
+    ("info", "Info"),
+    ("debug", "Debug"),
+    ("trace", "Trace"),
+];
+
+impl Options {
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("level") {
+            let level: Ident = meta.value()?.parse()?;
+            if !LEVELS.iter().any(|(name, _)| level == name) {
+                return Err(Error::new(
+                    level.span(),
+                    "expected one of `error`, `warn`, `info`, `debug`, `trace`",
+                ));
+            }
+            self.level = level;
+        } else if meta.path.is_ident("skip") {
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("return") {
+                    self.skip_return = true;
+                } else {
+                    self.skip.push(meta.path.require_ident()?.clone());
+                }
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("hex") {
+            meta.parse_nested_meta(|meta| {
+                self.hex.push(meta.path.require_ident()?.clone());
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("elapsed") {
+            self.elapsed = true;
+        } else {
+            return Err(meta.error("expected `level`, `skip`, `hex` or `elapsed`"));
+        }
+        Ok(())
+    }
+}
+
+/// Finds `impl Trait`, which cannot annotate the binding of the return value.
+#[derive(Default)]
+struct ImplTrait(bool);
+
+impl Visit<'_> for ImplTrait {
+    fn visit_type_impl_trait(&mut self, _: &syn::TypeImplTrait) {
+        self.0 = true;
+    }
+}
+
+pub fn expand(options: Options, function: syn::ItemFn) -> Result<TokenStream> {
+    let syn::ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+    if let Some(asyncness) = sig.asyncness {
+        return Err(Error::new(
+            asyncness.span(),
+            "async functions are not supported",
+        ));
+    }
+    if let Some(constness) = sig.constness {
+        return Err(Error::new(
+            constness.span(),
+            "const functions are not supported",
+        ));
+    }
+
+    let params: Vec<&Ident> = sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+                syn::Pat::Ident(pat) => Some(&pat.ident),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect();
+    for ident in options.skip.iter().chain(&options.hex) {
+        if !params.contains(&ident) {
+            return Err(Error::new(ident.span(), "no such argument"));
+        }
+    }
+
+    let name = sig.ident.to_string();
+    let mut entry = Vec::new();
+    let mut entry_args = Vec::new();
+    for ident in params {
+        if options.skip.contains(ident) {
+            continue;
+        }
+        let param = ident.to_string();
+        let param = param.strip_prefix("r#").unwrap_or(&param);
+        if options.hex.contains(ident) {
+            entry.push(format!("{}: {{}}", param));
+            entry_args.push(quote!(::delog::hex_str!(&#ident)));
+        } else {
+            entry.push(format!("{}: {{:?}}", param));
+            entry_args.push(quote!(#ident));
+        }
+    }
+    let entry = format!("enter {}({})", name, entry.join(", "));
+
+    let (returns_value, annotation) = match &sig.output {
+        syn::ReturnType::Default => (false, quote!()),
+        syn::ReturnType::Type(_, ty) => {
+            let unit = matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty());
+            let mut impl_trait = ImplTrait::default();
+            impl_trait.visit_type(ty);
+            let annotation = if impl_trait.0 {
+                quote!()
+            } else {
+                quote!(: #ty)
+            };
+            (!unit, annotation)
+        }
+    };
+
+    let level = &options.level;
+    let variant = LEVELS
+        .iter()
+        .find(|(name, _)| level == name)
+        .map(|(_, variant)| format_ident!("{}", variant))
+        .unwrap();
+    let log = format_ident!("{}", level.to_string());
+
+    // the exit, with the given format string and arguments, from the guard's fields
+    let log_exit = |format: String, args: TokenStream| {
+        if options.elapsed {
+            let format_elapsed = format!("{} (elapsed: {{}})", format);
+            quote! {
+                match self.start.and_then(|start| Some(::delog::now()?.wrapping_sub(start))) {
+                    Some(elapsed) => { #log!(#format_elapsed #args, elapsed); }
+                    None => { #log!(#format #args); }
+                }
+            }
+        } else {
+            quote!(#log!(#format #args);)
+        }
+    };
+    let early_exit = log_exit(format!("exit {}", name), quote!());
+    let (exit, bound) = if returns_value && !options.skip_return {
+        let format = format!("exit {} -> {{:?}}", name);
+        (
+            log_exit(format, quote!(, __delog_return)),
+            quote!(: ::core::fmt::Debug),
+        )
+    } else {
+        (log_exit(format!("exit {}", name), quote!()), quote!())
+    };
+    let start = if options.elapsed {
+        quote!(if __delog_enabled {
+            ::delog::now()
+        } else {
+            None
+        })
+    } else {
+        quote!(None)
+    };
+
+    // with the level compiled out, `__delog_enabled` is a constant `false`
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __delog_enabled = log_enabled!(::delog::Level::#variant);
+            if __delog_enabled {
+                #log!(#entry #(, #entry_args)*);
+            }
+            // logs the exit when dropped, unless the return value was logged
+            struct __DelogExit {
+                enabled: bool,
+                start: Option<u64>,
+            }
+            impl __DelogExit {
+                fn exit<T #bound>(mut self, __delog_return: &T) {
+                    if ::core::mem::take(&mut self.enabled) {
+                        #exit
+                    }
+                }
+            }
+            impl Drop for __DelogExit {
+                fn drop(&mut self) {
+                    if self.enabled {
+                        #early_exit
+                    }
+                }
+            }
+            let __delog_exit = __DelogExit {
+                enabled: __delog_enabled,
+                start: #start,
+            };
+            let __delog_return #annotation = #block;
+            #[allow(unreachable_code)]
+            __delog_exit.exit(&__delog_return);
+            __delog_return
+        }
+    })
+}
@@ -0,0 +1,59 @@
+//! Procedural macros for `delog`, re-exported by it under the `macros` feature.
+//!
+//! Use them via `delog`, not directly: the expansions refer to `::delog`, and to the
+//! macros `delog::generate_macros!()` defines in the calling crate.
+
+use proc_macro::TokenStream;
+
+mod instrument;
+mod log_debug;
+
+/// Log entry to and exit from a function, via the calling crate's generated macros.
+///
+/// On entry, the arguments are logged with `Debug`; on exit, the return value, except when
+/// leaving early via `return`, `?` or a panic, which are logged without it.
+/// The calling crate's gating of the level applies, as for direct calls of its `trace!` etc.,
+/// so with the level compiled out, nothing is logged and the clock is not read.
+///
+/// Options, all optional:
+/// - `level = debug`: the level to log at, default `trace`
+/// - `skip(arg, ..)`: arguments not to log, or `return` for the return value
+/// - `hex(arg, ..)`: arguments to format with `delog::hex_str!`
+/// - `elapsed`: also log the time spent in the function, via `delog::now()`
+///
+/// Receivers and arguments bound by patterns other than identifiers are not logged.
+/// Async and const functions are not supported.
+///
+/// See `delog`'s documentation for an example.
+#[proc_macro_attribute]
+pub fn instrument(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = instrument::Options::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    syn::parse_macro_input!(args with parser);
+    let function = syn::parse_macro_input!(item as syn::ItemFn);
+
+    instrument::expand(options, function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Debug`, formatting fields compactly as configured by `#[log(..)]` attributes.
+///
+/// Field options:
+/// - `hex`: format with `delog::hex_str!`
+/// - `max = n`: truncate to `n` bytes with `hex`, otherwise to `n` characters via `delog::trunc!`
+/// - `skip`: leave out the field, marking the struct as non-exhaustive, as in `Key { id: 7, .. }`
+/// - `secret`: wrap in `delog::Secret`, redacting the value unless `reveal-secrets` is active
+///
+/// Fields without options are formatted via their `Debug` implementation, as with
+/// `derive(Debug)`. Structs and enums are supported.
+///
+/// See `delog`'s documentation for an example.
+#[proc_macro_derive(LogDebug, attributes(log))]
+pub fn log_debug(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    log_debug::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,154 @@
+//! The `LogDebug` derive.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Data, DeriveInput, Error, Fields, LitInt, Result};
+
+#[derive(Default)]
+struct Options {
+    hex: bool,
+    max: Option<LitInt>,
+    skip: bool,
+    secret: bool,
+}
+
+impl Options {
+    fn of(field: &syn::Field) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("log"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("hex") {
+                    options.hex = true;
+                } else if meta.path.is_ident("max") {
+                    options.max = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else if meta.path.is_ident("secret") {
+                    options.secret = true;
+                } else {
+                    return Err(meta.error("expected `hex`, `max`, `skip` or `secret`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+
+    /// The value to format in place of the field, bound by reference as `binding`.
+    fn value(&self, binding: &syn::Ident) -> TokenStream {
+        let value = match (self.hex, &self.max) {
+            (true, None) => quote!(::delog::hex_str!(#binding)),
+            (true, Some(max)) => quote! {
+                ::delog::hex_str!(#binding)
+                    .truncation(::delog::hex::Truncation::new().max_bytes(#max))
+            },
+            (false, Some(max)) => quote!(::delog::trunc!(#binding, #max)),
+            (false, None) => quote!(#binding),
+        };
+        if self.secret {
+            quote!(::delog::Secret::new(#value))
+        } else {
+            value
+        }
+    }
+}
+
+/// The pattern binding the logged fields, and the `Formatter` calls formatting them.
+fn fields(name: &str, path: TokenStream, fields: &Fields) -> Result<TokenStream> {
+    let mut bindings = Vec::new();
+    let mut calls = Vec::new();
+    let mut skipped = false;
+    for (i, field) in fields.iter().enumerate() {
+        let options = Options::of(field)?;
+        let binding = format_ident!("__field_{}", i);
+        if options.skip {
+            skipped = true;
+            bindings.push(match &field.ident {
+                Some(_) => quote!(),
+                None => quote!(_),
+            });
+            continue;
+        }
+        let value = options.value(&binding);
+        match &field.ident {
+            Some(ident) => {
+                let label = ident.to_string();
+                let label = label.strip_prefix("r#").unwrap_or(&label);
+                bindings.push(quote!(#ident: #binding));
+                calls.push(quote!(.field(#label, &#value)));
+            }
+            None => {
+                bindings.push(quote!(#binding));
+                calls.push(quote!(.field(&#value)));
+            }
+        }
+    }
+
+    Ok(match fields {
+        Fields::Named(_) => {
+            let bindings = bindings.iter().filter(|binding| !binding.is_empty());
+            let finish = if skipped {
+                quote!(finish_non_exhaustive)
+            } else {
+                quote!(finish)
+            };
+            quote! {
+                #path { #(#bindings,)* .. } => f.debug_struct(#name) #(#calls)* .#finish()
+            }
+        }
+        Fields::Unnamed(_) => quote! {
+            #path(#(#bindings),*) => f.debug_tuple(#name) #(#calls)* .finish()
+        },
+        Fields::Unit => quote! {
+            #path => f.write_str(#name)
+        },
+    })
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let arms = match &input.data {
+        Data::Struct(data) => vec![fields(&ident.to_string(), quote!(Self), &data.fields)?],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let name = &variant.ident;
+                fields(&name.to_string(), quote!(Self::#name), &variant.fields)
+            })
+            .collect::<Result<_>>()?,
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "unions are not supported",
+            ))
+        }
+    };
+
+    // like `derive(Debug)`, require `Debug` of all type parameters
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(::core::fmt::Debug));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // without variants, there is no value to match on by reference
+    let scrutinee = if arms.is_empty() {
+        quote!(*self)
+    } else {
+        quote!(self)
+    };
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match #scrutinee {
+                    #(#arms,)*
+                }
+            }
+        }
+    })
+}
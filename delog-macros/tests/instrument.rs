@@ -0,0 +1,121 @@
+use std::sync::Mutex;
+
+delog::generate_macros!(debug: ["traced"]);
+
+static LOGS: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Debug)]
+struct Flusher {}
+impl delog::Flusher for Flusher {
+    fn flush(&self, logs: &str) {
+        LOGS.lock().unwrap().push_str(logs);
+    }
+}
+
+delog::delog!(Delogger, 1024, Flusher, renderer: delog::render::DefaultRenderer);
+static FLUSHER: Flusher = Flusher {};
+
+mod traced {
+    #[delog::instrument(level = debug, hex(key), skip(scratch))]
+    pub fn encrypt(key: &[u8], nonce: u32, scratch: &mut [u8]) -> Result<usize, ()> {
+        if nonce == 0 {
+            return Err(());
+        }
+        scratch[0] = key[0] ^ nonce as u8;
+        Ok(scratch.len())
+    }
+
+    #[delog::instrument(level = info, skip(return), elapsed)]
+    pub fn clear(buf: &mut Vec<u8>) -> Vec<u8> {
+        core::mem::take(buf)
+    }
+
+    pub struct Counter(pub u32);
+
+    impl Counter {
+        #[delog::instrument(level = debug)]
+        pub fn add(&mut self, by: u32) -> impl core::fmt::Debug + '_ {
+            self.0 += by;
+            &self.0
+        }
+    }
+
+    #[delog::instrument(level = debug, elapsed)]
+    pub fn parse(s: &str) -> Result<u8, core::num::ParseIntError> {
+        let n = s.parse()?;
+        Ok(n)
+    }
+
+    // the return value is only logged at the end of the body
+    #[delog::instrument(level = debug)]
+    #[allow(clippy::needless_return)]
+    pub fn sign(x: i8) -> i8 {
+        if x < 0 {
+            return -1;
+        }
+        return x.signum();
+    }
+
+    // selected for debug, so trace is compiled out
+    #[delog::instrument]
+    pub fn quiet(x: u8) -> u8 {
+        x + 1
+    }
+}
+
+mod untraced {
+    #[delog::instrument(level = error)]
+    pub fn double(x: u8) -> u8 {
+        2 * x
+    }
+}
+
+fn ticks() -> u64 {
+    static TICKS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    TICKS.fetch_add(5, std::sync::atomic::Ordering::Relaxed)
+}
+
+#[test]
+fn instrument() {
+    Delogger::init(
+        delog::LevelFilter::Trace,
+        &FLUSHER,
+        delog::render::default(),
+    )
+    .ok();
+    delog::set_clock(ticks);
+
+    let mut scratch = [0u8; 4];
+    assert_eq!(traced::encrypt(&[0xA1, 0xB2], 3, &mut scratch), Ok(4));
+    assert_eq!(traced::encrypt(&[0xA1], 0, &mut scratch), Err(()));
+    assert_eq!(traced::clear(&mut vec![1, 2]), [1, 2]);
+    let mut counter = traced::Counter(1);
+    assert_eq!(format!("{:?}", counter.add(2)), "3");
+    assert_eq!(traced::parse("7"), Ok(7));
+    assert!(traced::parse("x").is_err());
+    assert_eq!(traced::sign(-3), -1);
+    assert_eq!(traced::sign(3), 1);
+    assert_eq!(traced::quiet(1), 2);
+    assert_eq!(untraced::double(2), 4);
+
+    Delogger::flush();
+    assert_eq!(
+        *LOGS.lock().unwrap(),
+        "enter encrypt(key: A1 B2, nonce: 3)\n\
+         exit encrypt -> Ok(4)\n\
+         enter encrypt(key: A1, nonce: 0)\n\
+         exit encrypt\n\
+         enter clear(buf: [1, 2])\n\
+         exit clear (elapsed: 5)\n\
+         enter add(by: 2)\n\
+         exit add -> 3\n\
+         enter parse(s: \"7\")\n\
+         exit parse -> Ok(7) (elapsed: 5)\n\
+         enter parse(s: \"x\")\n\
+         exit parse (elapsed: 5)\n\
+         enter sign(x: -3)\n\
+         exit sign\n\
+         enter sign(x: 3)\n\
+         exit sign\n",
+    );
+}
@@ -0,0 +1,78 @@
+use delog::LogDebug;
+
+#[derive(LogDebug)]
+struct Session {
+    id: u16,
+    #[log(hex, max = 4)]
+    challenge: [u8; 8],
+    #[log(hex)]
+    nonce: Vec<u8>,
+    #[log(max = 6)]
+    label: &'static str,
+    #[log(secret)]
+    pin: u32,
+    #[log(skip)]
+    #[allow(dead_code)]
+    scratch: [u8; 64],
+}
+
+#[derive(LogDebug)]
+#[allow(dead_code)]
+struct Key(#[log(hex, secret)] [u8; 2], #[log(skip)] u8, u8);
+
+#[derive(LogDebug)]
+enum Command<T> {
+    Reset,
+    Write(u32, #[log(hex, max = 2)] Vec<u8>),
+    Select {
+        #[log(hex)]
+        aid: [u8; 3],
+        extra: T,
+    },
+}
+
+#[derive(LogDebug)]
+enum Never {}
+
+#[test]
+fn log_debug() {
+    let session = Session {
+        id: 7,
+        challenge: [1, 2, 3, 4, 5, 6, 7, 8],
+        nonce: vec![0xA1, 0xB2],
+        label: "hello, world",
+        pin: 1234,
+        scratch: [0; 64],
+    };
+    // redacted, unless `reveal-secrets` is active
+    let pin = format!("{:?}", delog::Secret::new(1234));
+    assert_eq!(
+        format!("{:?}", session),
+        format!(
+            "Session {{ id: 7, challenge: 01 02..07 08, nonce: A1 B2, label: \"hello.., pin: {}, .. }}",
+            pin
+        ),
+    );
+    let key = format!("{:?}", delog::Secret::new(delog::hex_str!(&[0xFF, 0])));
+    assert_eq!(
+        format!("{:?}", Key([0xFF, 0], 1, 2)),
+        format!("Key({}, 2)", key)
+    );
+
+    assert_eq!(format!("{:?}", Command::<()>::Reset), "Reset");
+    assert_eq!(
+        format!("{:?}", Command::<()>::Write(3, vec![1, 2, 3])),
+        "Write(3, 01..03)"
+    );
+    assert_eq!(
+        format!(
+            "{:#?}",
+            Command::Select {
+                aid: [0xA0, 0, 1],
+                extra: Some(1)
+            }
+        ),
+        "Select {\n    aid: A0 00 01,\n    extra: Some(\n        1,\n    ),\n}",
+    );
+    let _ = |never: &Never| format!("{:?}", never);
+}
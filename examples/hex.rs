@@ -1,4 +1,4 @@
-use delog::{hex_str, hexstr};
+use delog::{hex_str, hex_words, hexdump, hexstr};
 
 fn main() {
     let buf = [1u8, 2, 3, 0xA1, 0xB7, 0xFF, 0x3];
@@ -23,4 +23,8 @@ fn main() {
     println!("'{:4}'", hex_str!(&buf, 2, sep: "|"));
     println!("'{:5x}'", hex_str!(&buf, 2, sep: "|"));
     println!("'{:6}'", hex_str!(&buf, 2, sep: "|"));
+    println!("{}", hexdump!(&buf));
+    println!("{:<4}", hexdump!(&buf));
+    println!("'{}'", hex_words!(&[0x1u16, 0xA1B7, 0xFF03]));
+    println!("'{}'", hex_words!(&buf, le: 4));
 }
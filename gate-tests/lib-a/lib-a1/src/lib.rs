@@ -1,4 +1,5 @@
-delog::generate_macros!();
+// warnings in `submodule` are compiled in even without `log-*` features
+delog::generate_macros!(warn: ["submodule"]);
 
 pub fn f() {
     submodule::sub_f()
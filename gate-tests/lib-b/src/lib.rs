@@ -3,6 +3,8 @@ delog::generate_macros!();
 use delog::log;
 
 pub fn g() {
+    time_scope!(delog::Level::Info, "g from B");
+
     log::info!("global info from B");
     warn!("info from B");
 
@@ -16,4 +18,22 @@ pub fn g() {
     error!("another error from B");
     info!(target: "!", "immediate local info from B");
     warn!(target: "!", "immediate local warn from B");
+
+    if log_enabled!(delog::Level::Info) {
+        let summary: u32 = (0..1000).sum();
+        info!("expensive summary from B: {}", summary);
+    }
+
+    for i in 0..10 {
+        info_once!("polling from B");
+        warn_every_n!(4, "poll {} from B", i);
+        error_throttled!(1000, "throttled poll {} from B", i);
+    }
+
+    log_assert_eq!(checksum(&[0xB0, 0x0B], 7), 194);
+}
+
+#[delog::instrument(level = info, hex(data), elapsed)]
+fn checksum(data: &[u8], seed: u8) -> u8 {
+    data.iter().fold(seed, |sum, byte| sum.wrapping_add(*byte))
 }
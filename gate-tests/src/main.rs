@@ -7,7 +7,7 @@ cfg_if::cfg_if! {
     if #[cfg(feature = "verbose-renderer")] {
         use delog::render::RipgrepRenderer;
         delog!(Delogger, 4096, StdoutFlusher, renderer: RipgrepRenderer);
-        static RENDERER: RipgrepRenderer = RipgrepRenderer {};
+        static RENDERER: RipgrepRenderer = RipgrepRenderer::new();
     } else {
         use delog::render::DefaultRenderer;
         delog!(Delogger, 4096, StdoutFlusher);
@@ -17,8 +17,15 @@ cfg_if::cfg_if! {
 
 static FLUSHER: StdoutFlusher = StdoutFlusher {};
 
+fn millis() -> u64 {
+    let since_epoch = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    since_epoch.expect("after 1970").as_millis() as u64
+}
+
 fn main() {
     Delogger::init(delog::LevelFilter::Info, &FLUSHER, &RENDERER).expect("all good");
+    delog::set_clock(millis);
+    delog::panic::install_hook();
     lib_a::f();
     lib_b::g();
     println!("{:?}", delog::logger().unwrap().statistics());
@@ -0,0 +1,287 @@
+//! Convenient `Display` for binary data in base64, base32 and base58.
+//!
+//! Like the `hex` module, this wraps byte arrays and slices in newtypes that format
+//! without allocation. Truncation works like for `hex_str!`, except that the width
+//! counts encoded characters, so the shown parts agree with the full encoding.
+//!
+//! ```
+//! use delog::{b32, b58, b64, b64url};
+//!
+//! let data = b"\xfb\xff delog";
+//!
+//! assert_eq!(format!("{}", b64!(data)), "+/8gZGVsb2c=");
+//! assert_eq!(format!("{}", b64url!(data)), "-_8gZGVsb2c");
+//! assert_eq!(format!("{}", b32!(data)), "7P7SAZDFNRXWO===");
+//! assert_eq!(format!("{}", b58!(data)), "j9gbA7PUENz");
+//! assert_eq!(format!("{:6}", b64!(data)), "+/8..2c=");
+//! assert_eq!(format!("{:<6}", b64url!(data)), "-_8gZG..");
+//! assert_eq!(format!("{:>6}", b58!(data)), "..7PUENz");
+//! ```
+
+use core::fmt;
+
+/// Wrapper newtype, formatting bytes in standard base64, with padding.
+pub struct Base64<'a, T: ?Sized>(pub &'a T);
+
+/// Wrapper newtype, formatting bytes in URL-safe base64, without padding.
+pub struct Base64Url<'a, T: ?Sized>(pub &'a T);
+
+/// Wrapper newtype, formatting bytes in standard base32, with padding.
+pub struct Base32<'a, T: ?Sized>(pub &'a T);
+
+/// Wrapper newtype, formatting bytes in base58, with the Bitcoin alphabet.
+///
+/// Inputs longer than 256 bytes are formatted as `..`.
+pub struct Base58<'a, T: ?Sized>(pub &'a T);
+
+#[macro_export]
+/// Format byte arrays and slices in standard base64.
+///
+/// ```
+/// use delog::b64;
+/// assert_eq!(format!("{}", b64!(b"foobar")), "Zm9vYmFy");
+/// assert_eq!(format!("{}", b64!(b"fooba")), "Zm9vYmE=");
+/// ```
+macro_rules! b64 {
+    ($array:expr) => {
+        $crate::base::Base64($array)
+    };
+}
+
+#[macro_export]
+/// Format byte arrays and slices in URL-safe base64, without padding.
+///
+/// ```
+/// use delog::b64url;
+/// assert_eq!(format!("{}", b64url!(&[0xfbu8, 0xff])), "-_8");
+/// ```
+macro_rules! b64url {
+    ($array:expr) => {
+        $crate::base::Base64Url($array)
+    };
+}
+
+#[macro_export]
+/// Format byte arrays and slices in standard base32.
+///
+/// ```
+/// use delog::b32;
+/// assert_eq!(format!("{}", b32!(b"foobar")), "MZXW6YTBOI======");
+/// ```
+macro_rules! b32 {
+    ($array:expr) => {
+        $crate::base::Base32($array)
+    };
+}
+
+#[macro_export]
+/// Format byte arrays and slices in base58.
+///
+/// ```
+/// use delog::b58;
+/// assert_eq!(format!("{}", b58!(b"\0\0hello world")), "11StV1DL6CwTryKyV");
+/// ```
+macro_rules! b58 {
+    ($array:expr) => {
+        $crate::base::Base58($array)
+    };
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+const BASE58_MAX_BYTES: usize = 256;
+// log(256)/log(58) < 1.38
+const BASE58_MAX_DIGITS: usize = BASE58_MAX_BYTES * 138 / 100 + 1;
+
+/// Writes the characters of an encoding of known length, eliding the middle
+/// (or either end) if it exceeds the formatter's width.
+struct Truncated<'f, 'a> {
+    f: &'f mut fmt::Formatter<'a>,
+    position: usize,
+    // characters before and from which to write
+    left: usize,
+    right: usize,
+}
+
+impl<'f, 'a> Truncated<'f, 'a> {
+    fn new(f: &'f mut fmt::Formatter<'a>, len: usize) -> Self {
+        use core::fmt::Alignment::*;
+        let max_chars = f.width().unwrap_or(usize::MAX);
+        let (left, right) = if len <= max_chars {
+            (len, 0)
+        } else {
+            match f.align().unwrap_or(Center) {
+                Left => (max_chars, 0),
+                Center => (max_chars - max_chars / 2, max_chars / 2),
+                Right => (0, max_chars),
+            }
+        };
+        Self {
+            f,
+            position: 0,
+            left,
+            right: len - right,
+        }
+    }
+
+    fn push(&mut self, c: u8) -> fmt::Result {
+        let position = self.position;
+        self.position += 1;
+        if position == self.left && self.left < self.right {
+            self.f.write_str("..")?;
+        }
+        if position < self.left || position >= self.right {
+            fmt::Write::write_char(self.f, c as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes in groups of `bits`, as base64 and base32 do.
+fn encode_bits(
+    bytes: &[u8],
+    f: &mut fmt::Formatter<'_>,
+    alphabet: &[u8],
+    bits: usize,
+    padded_block: Option<usize>,
+) -> fmt::Result {
+    let mut len = (bytes.len() * 8).div_ceil(bits);
+    let unpadded = len;
+    if let Some(block) = padded_block {
+        len = len.div_ceil(block) * block;
+    }
+    let mask = (1 << bits) - 1;
+
+    let mut out = Truncated::new(f, len);
+    let mut buffer = 0u32;
+    let mut buffered = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        buffered += 8;
+        while buffered >= bits {
+            buffered -= bits;
+            out.push(alphabet[(buffer >> buffered) as usize & mask])?;
+        }
+    }
+    if buffered > 0 {
+        out.push(alphabet[(buffer << (bits - buffered)) as usize & mask])?;
+    }
+    for _ in unpadded..len {
+        out.push(b'=')?;
+    }
+    Ok(())
+}
+
+fn base64(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    encode_bits(bytes, f, BASE64, 6, Some(4))
+}
+
+fn base64url(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    encode_bits(bytes, f, BASE64_URL, 6, None)
+}
+
+fn base32(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    encode_bits(bytes, f, BASE32, 5, Some(8))
+}
+
+fn base58(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if bytes.len() > BASE58_MAX_BYTES {
+        return f.write_str("..");
+    }
+
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+
+    // little-endian base58 digits of the remaining big-endian number
+    let mut digits = [0u8; BASE58_MAX_DIGITS];
+    let mut len = 0;
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits[..len].iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits[len] = (carry % 58) as u8;
+            len += 1;
+            carry /= 58;
+        }
+    }
+
+    let mut out = Truncated::new(f, zeros + len);
+    for _ in 0..zeros {
+        out.push(BASE58[0])?;
+    }
+    for &digit in digits[..len].iter().rev() {
+        out.push(BASE58[digit as usize])?;
+    }
+    Ok(())
+}
+
+macro_rules! implement {
+    ($Type:ident, $encode:expr) => {
+        impl<T: ?Sized + AsRef<[u8]>> fmt::Debug for $Type<'_, T> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl<T: ?Sized + AsRef<[u8]>> fmt::Display for $Type<'_, T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                $encode(self.0.as_ref(), f)
+            }
+        }
+    };
+}
+
+implement!(Base64, base64);
+implement!(Base64Url, base64url);
+implement!(Base32, base32);
+implement!(Base58, base58);
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_rfc4648() {
+        let vectors = [
+            ("", "", ""),
+            ("f", "Zg==", "MY======"),
+            ("fo", "Zm8=", "MZXQ===="),
+            ("foo", "Zm9v", "MZXW6==="),
+            ("foob", "Zm9vYg==", "MZXW6YQ="),
+            ("fooba", "Zm9vYmE=", "MZXW6YTB"),
+            ("foobar", "Zm9vYmFy", "MZXW6YTBOI======"),
+        ];
+        for (data, base64, base32) in vectors {
+            assert_eq!(format!("{}", b64!(data)), base64);
+            assert_eq!(format!("{}", b64url!(data)), base64.trim_end_matches('='));
+            assert_eq!(format!("{}", b32!(data)), base32);
+        }
+    }
+
+    #[test]
+    fn test_base58() {
+        assert_eq!(format!("{}", b58!(b"")), "");
+        assert_eq!(format!("{}", b58!(&[0u8])), "1");
+        assert_eq!(
+            format!("{}", b58!(&[0u8, 0, 0x28, 0x7f, 0xb4, 0xcd])),
+            "11233QC4"
+        );
+        assert_eq!(format!("{}", b58!(b"Hello World!")), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(format!("{}", b58!(&[0xffu8; 300][..])), "..");
+    }
+
+    #[test]
+    fn test_truncation() {
+        assert_eq!(format!("{:4}", b64!(b"foobar")), "Zm..Fy");
+        assert_eq!(format!("{:<4}", b64!(b"foobar")), "Zm9v..");
+        assert_eq!(format!("{:>4}", b64!(b"foobar")), "..YmFy");
+        assert_eq!(format!("{:<1}", b64!(b"foobar")), "Z..");
+        assert_eq!(format!("{:8}", b64!(b"foobar")), "Zm9vYmFy");
+        assert_eq!(format!("{:>3}", b32!(b"foobar")), "..===");
+    }
+}
@@ -35,6 +35,11 @@ impl crate::Flusher for StderrFlusher {
     fn flush(&self, logs: &str) {
         eprint!("{}", logs);
     }
+
+    fn flush_bytes(&self, logs: &[u8]) {
+        use std::io::Write;
+        std::io::stderr().write_all(logs).ok();
+    }
 }
 
 #[derive(Debug, Default)]
@@ -45,6 +50,11 @@ impl crate::Flusher for StdoutFlusher {
     fn flush(&self, logs: &str) {
         print!("{}", logs);
     }
+
+    fn flush_bytes(&self, logs: &[u8]) {
+        use std::io::Write;
+        std::io::stdout().write_all(logs).ok();
+    }
 }
 
 crate::delog!(Delogger, 4096, StderrFlusher, renderer: DefaultRenderer);
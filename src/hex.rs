@@ -32,7 +32,15 @@
 //! ```
 
 use core::fmt;
-use core::marker::PhantomData;
+
+mod bytes;
+mod diff;
+mod parse;
+mod words;
+pub use bytes::ByteStr;
+pub use diff::HexDiff;
+pub use parse::{decode, decode_blocks, Decoded, ParseError};
+pub use words::{Endian, HexEndian, HexWords, Word};
 
 ///// re-export from `hex_fmt`
 /////
@@ -41,79 +49,136 @@ use core::marker::PhantomData;
 /////
 //pub use hex_fmt::HexList;
 
-/// A type that specifies an unsigned integer.
+/// Wrapper newtype, allowing grouping bytes in blocks of `N` hexadecimals
+/// during formatting.
+///
+/// Use the `hex_str!` or `hexstr!` macro to construct this from your byte array or slice,
+/// or explicitly:
+///
+/// ```
+/// use delog::hex::HexStr;
+///
+/// let four_bytes = &[7u8, 0xA1, 255, 0xC7];
+/// let hex_str = HexStr::<_, 3>::new(four_bytes).separator("|");
+///
+/// assert_eq!(format!("{}", hex_str), "07A1FF|C7");
+/// ```
 ///
-/// We use this instead of `typenum` as the latter currently lacks
-/// a mapping from `usize` to the associated type.
-pub trait Unsigned {
-    /// The actual number.
-    const N: usize;
+/// The block size only determines the type; all block sizes and separators share
+/// the same formatting implementation.
+pub struct HexStr<'a, T: ?Sized, const N: usize = 1> {
+    /// The value to be formatted.
+    pub value: &'a T,
+    separator: &'static str,
+    truncation: Truncation,
 }
 
-/// Sorry little replacement for the missing int to Unsigned type map in `typenum`.
-#[macro_export]
-#[doc(hidden)]
-macro_rules! typeint {
-    ($name:ident, $n:expr) => {
-        /// A type that represents the integer `N`.
-        pub struct $name;
-        impl $crate::hex::Unsigned for $name {
-            const N: usize = $n;
+impl<'a, T: ?Sized, const N: usize> HexStr<'a, T, N> {
+    /// Wrap `value`, separating blocks by spaces.
+    pub const fn new(value: &'a T) -> Self {
+        Self {
+            value,
+            separator: " ",
+            truncation: Truncation::new(),
         }
-    };
-}
+    }
+
+    /// Set the separator between blocks.
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
 
-/// A type that represents the integer `1`.
-pub struct U1;
-impl Unsigned for U1 {
-    const N: usize = 1;
+    /// Set how output truncated to the formatter's width is marked.
+    pub const fn truncation(mut self, truncation: Truncation) -> Self {
+        self.truncation = truncation;
+        self
+    }
 }
 
-// /// A type representing the number 1.
-// pub struct U1 {}
-// impl Unsigned for U1 {
-//     const N: usize = 1;
-// }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// How `HexStr` marks bytes omitted due to the formatter's width.
+///
+/// By default, the omitted bytes are replaced by `..`, without separators.
+/// Usually the formatter's width determines truncation, see `max_bytes` for a fixed maximum.
+/// Note that `decode` only recognizes the default markers as truncation.
+///
+/// ```
+/// use delog::{hex::Truncation, hex_str};
+///
+/// let data: [u8; 252] = core::array::from_fn(|i| i as u8);
+/// let counted = Truncation::new().count().separated();
+/// assert_eq!(
+///     format!("{:4x}", hex_str!(&data).truncation(counted)),
+///     "00 01 ..(248 bytes).. fa fb",
+/// );
+/// let marked = Truncation::new().marker("…").total();
+/// assert_eq!(format!("{:<2x}", hex_str!(&data).truncation(marked)), "[252] 00 01…");
+/// ```
+pub struct Truncation {
+    marker: &'static str,
+    count: bool,
+    separated: bool,
+    total: bool,
+    max_bytes: usize,
+}
 
-/// A type that specifies a separator str.
-pub trait Separator {
-    /// The actual separator str.
-    const SEPARATOR: &'static str;
+impl Default for Truncation {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[macro_export]
-#[doc(hidden)]
-macro_rules! typesep {
-    ($name:ident, $s:expr) => {
-        /// A type that represents the integer `N`.
-        pub struct $name;
-        impl $crate::hex::Separator for $name {
-            const SEPARATOR: &'static str = $s;
+impl Truncation {
+    /// The default truncation marker, `..`.
+    pub const fn new() -> Self {
+        Self {
+            marker: "..",
+            count: false,
+            separated: false,
+            total: false,
+            max_bytes: usize::MAX,
         }
-    };
-}
+    }
 
-// /// New approach.
-// pub trait HexStrTrait {
-//     const BYTES_PER_BLOCK: usize;
-//     const SEPARATOR: &'static str;
-//     fn bytes(&self) -> &[u8];
-// }
+    /// Replace omitted bytes by `marker` instead of `..`.
+    pub const fn marker(mut self, marker: &'static str) -> Self {
+        self.marker = marker;
+        self
+    }
 
-/// Zero-sized wrapper newtype, allowing grouping bytes in blocks of N hexadecimals
-/// during formatting.
-///
-/// Use the method with the same name to construct this from your byte array or slice,
-/// or preferrably the `hex_str!` or `hexstr!` macro.
-pub struct HexStr<'a, T: ?Sized, U, S>
-where
-    U: Unsigned,
-    S: Separator,
-{
-    /// The value to be formatted.
-    pub value: &'a T,
-    _bytes_per_block: PhantomData<U>,
-    _separator: PhantomData<S>,
+    /// Include the number of omitted bytes, as in `..(248 bytes)..`.
+    pub const fn count(mut self) -> Self {
+        self.count = true;
+        self
+    }
+
+    /// Separate the marker from the shown blocks by the block separator.
+    pub const fn separated(mut self) -> Self {
+        self.separated = true;
+        self
+    }
+
+    /// Prefix the output with the total number of bytes, as in `[252] `.
+    pub const fn total(mut self) -> Self {
+        self.total = true;
+        self
+    }
+
+    /// Truncate to at most `max_bytes` also without a formatter width, or with a larger one.
+    ///
+    /// ```
+    /// use delog::{hex::Truncation, hex_str};
+    ///
+    /// let data = [0xA1u8; 10];
+    /// let max_4 = Truncation::new().max_bytes(4);
+    /// assert_eq!(format!("{:?}", hex_str!(&data).truncation(max_4)), "A1 A1..A1 A1");
+    /// assert_eq!(format!("{:<2}", hex_str!(&data).truncation(max_4)), "A1 A1..");
+    /// ```
+    pub const fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
 }
 
 #[macro_export]
@@ -133,11 +198,9 @@ macro_rules! hex_str {
     ($array:expr) => { $crate::hex_str!($array, 1, sep: " ") };
     ($array:expr, sep: $separator:expr) => { $crate::hex_str!($array, 1, sep: $separator) };
     ($array:expr, $n:tt) => { $crate::hex_str!($array, $n, sep: " ") };
-    ($array:expr, $n:tt, sep: $separator:expr) => {{
-        $crate::typesep!(Separator, $separator);
-        $crate::typeint!(Number, $n);
-        $crate::hex::HexStr::<_, Number, Separator>($array)
-    }};
+    ($array:expr, $n:tt, sep: $separator:expr) => {
+        $crate::hex::HexStr::<_, $n>::new($array).separator($separator)
+    };
 }
 
 #[macro_export]
@@ -155,48 +218,9 @@ macro_rules! hexstr {
     }
 }
 
-#[allow(non_snake_case)]
-/// Explicitly construct a newtype to format with.
-///
-/// The first generic parameter specifies the block size in bytes, the second parameter
-/// the separator string.
-///
-/// This function is just here for documentation of what the `hex_str!` macro
-/// does internally (using the undocumented `typeint!` and `typesep!` macros).
-///
-/// ```
-/// use delog::hex::{HexStr, Separator, Unsigned};
-///
-/// // typeint!(U3, 3);
-/// struct U3 {}
-/// impl Unsigned for U3 {
-///     const N: usize = 3;
-/// }
-///
-/// // typesep!(Pipe, "|");
-/// struct Pipe {}
-/// impl Separator for Pipe {
-///     const SEPARATOR: &'static str  = "|";
-/// }
-///
-/// let four_bytes = &[7u8, 0xA1, 255, 0xC7];
-/// let hex_str = HexStr::<_, U3, Pipe>(four_bytes);
-///
-/// assert_eq!(format!("{}", hex_str), "07A1FF|C7");
-/// ```
-pub fn HexStr<T: ?Sized, U: Unsigned, S: Separator>(value: &T) -> HexStr<T, U, S> {
-    HexStr {
-        value,
-        _bytes_per_block: PhantomData,
-        _separator: PhantomData,
-    }
-}
-
-impl<T: ?Sized, S, U> fmt::Debug for HexStr<'_, T, U, S>
+impl<T: ?Sized, const N: usize> fmt::Debug for HexStr<'_, T, N>
 where
     T: AsRef<[u8]>,
-    U: Unsigned,
-    S: Separator,
 {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -204,11 +228,9 @@ where
     }
 }
 
-impl<T: ?Sized, U, S> fmt::Display for HexStr<'_, T, U, S>
+impl<T: ?Sized, const N: usize> fmt::Display for HexStr<'_, T, N>
 where
     T: AsRef<[u8]>,
-    U: Unsigned,
-    S: Separator,
 {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -216,77 +238,231 @@ where
     }
 }
 
+const LOWER_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const UPPER_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+fn nontruncated_fmt(
+    bytes: &[u8],
+    f: &mut fmt::Formatter<'_>,
+    chunk_size: usize,
+    separator: &str,
+    digits: &[u8; 16],
+) -> Result<(), fmt::Error> {
+    use fmt::Write;
+    let mut first = true;
+    for entry in bytes.chunks(chunk_size) {
+        if first {
+            first = false;
+        } else {
+            f.write_str(separator)?;
+        }
+        for byte in entry.iter() {
+            f.write_char(digits[(byte >> 4) as usize] as char)?;
+            f.write_char(digits[(byte & 0xf) as usize] as char)?;
+        }
+    }
+    Ok(())
+}
+
+/// The formatting shared by all `HexStr`.
+fn hex_str(
+    bytes: &[u8],
+    f: &mut fmt::Formatter<'_>,
+    chunk_size: usize,
+    separator: &str,
+    truncation: &Truncation,
+    digits: &[u8; 16],
+) -> Result<(), fmt::Error> {
+    use core::fmt::Alignment::*;
+    let max_bytes = f.width().unwrap_or(usize::MAX).min(truncation.max_bytes);
+    // `chunks` panics on zero
+    let chunk_size = chunk_size.max(1);
+
+    if truncation.total {
+        write!(f, "[{}] ", bytes.len())?;
+    }
+
+    if bytes.len() <= max_bytes {
+        nontruncated_fmt(bytes, f, chunk_size, separator, digits)
+    } else {
+        let align = f.align().unwrap_or(Center);
+        let (left, right) = match align {
+            Left => (max_bytes, 0),
+            Center => (max_bytes - max_bytes / 2, max_bytes / 2),
+            Right => (0, max_bytes),
+        };
+        nontruncated_fmt(&bytes[..left], f, chunk_size, separator, digits)?;
+        if truncation.separated && left > 0 {
+            f.write_str(separator)?;
+        }
+        f.write_str(truncation.marker)?;
+        if truncation.count {
+            write!(f, "({} bytes)", bytes.len() - left - right)?;
+            f.write_str(truncation.marker)?;
+        }
+        if truncation.separated && right > 0 {
+            f.write_str(separator)?;
+        }
+        nontruncated_fmt(
+            &bytes[bytes.len() - right..],
+            f,
+            chunk_size,
+            separator,
+            digits,
+        )?;
+        Ok(())
+    }
+}
+
 macro_rules! implement {
-    ($Trait:ident, $padded_formatter:expr) => {
-        impl<'a, T: ?Sized, U, S> fmt::$Trait for HexStr<'a, T, U, S>
+    ($Trait:ident, $digits:expr) => {
+        impl<'a, T: ?Sized, const N: usize> fmt::$Trait for HexStr<'a, T, N>
         where
             T: AsRef<[u8]>,
-            U: Unsigned,
-            S: Separator,
         {
+            #[inline]
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-                use core::fmt::{self, Alignment::*};
-                let max_bytes = f.width().unwrap_or(usize::MAX);
-                let bytes = self.value.as_ref();
-
-                #[inline]
-                fn nontruncated_fmt(
-                    bytes: &[u8],
-                    f: &mut fmt::Formatter<'_>,
-                    chunk_size: usize,
-                    separator: &str,
-                ) -> Result<(), fmt::Error> {
-                    let mut first = true;
-                    for entry in bytes.chunks(chunk_size) {
-                        if first {
-                            first = false;
-                        } else {
-                            f.write_str(separator)?;
-                        }
-                        for byte in entry.iter() {
-                            write!(f, $padded_formatter, byte)?;
-                        }
-                    }
-                    Ok(())
-                }
-
-                // const ELLIPSIS: &str = "…";
-                const ELLIPSIS: &str = "..";
-
-                let chunk_size = U::N;
-                let separator = S::SEPARATOR;
-
-                if bytes.len() <= max_bytes {
-                    nontruncated_fmt(bytes, f, chunk_size, separator)
-                } else {
-                    let align = f.align().unwrap_or(Center);
-                    let (left, right) = match align {
-                        Left => (max_bytes, 0),
-                        Center => (max_bytes - max_bytes / 2, max_bytes / 2),
-                        Right => (0, max_bytes),
-                    };
-                    nontruncated_fmt(&bytes[..left], f, chunk_size, separator)?;
-                    // if left > 0 {
-                    //     f.write_str(separator)?;
-                    // }
-                    f.write_str(ELLIPSIS)?;
-                    // if right > 0 {
-                    //     f.write_str(separator)?;
-                    // }
-                    nontruncated_fmt(&bytes[bytes.len() - right..], f, chunk_size, separator)?;
-                    Ok(())
-                }
+                hex_str(
+                    self.value.as_ref(),
+                    f,
+                    N,
+                    self.separator,
+                    &self.truncation,
+                    $digits,
+                )
             }
         }
     };
 }
 
-implement!(LowerHex, "{:02x}");
-implement!(UpperHex, "{:02X}");
+implement!(LowerHex, LOWER_DIGITS);
+implement!(UpperHex, UPPER_DIGITS);
+
+/// Wrapper newtype, formatting bytes like `hexdump -C` does.
+///
+/// Use the `hexdump!` macro to construct it.
+pub struct HexDump<'a, T: ?Sized>(pub &'a T);
+
+#[macro_export]
+/// Format byte arrays and slices in the canonical hex+ASCII display of `hexdump -C`.
+///
+/// Each row shows the offset, sixteen bytes in two groups of eight, and their printable
+/// ASCII characters; the last line shows the total length. Width and alignment are
+/// honoured like for `hex_str!`, with elided bytes replaced by a `..` line.
+///
+/// ```
+/// use delog::hexdump;
+/// let data = b"Hello, hexdump!\n\x00\x01";
+/// assert_eq!(
+///     format!("{}", hexdump!(data)),
+///     "00000000  48 65 6c 6c 6f 2c 20 68  65 78 64 75 6d 70 21 0a  |Hello, hexdump!.|\n\
+///      00000010  00 01                                             |..|\n\
+///      00000012",
+/// );
+/// assert_eq!(
+///     format!("{:<4}", hexdump!(data)),
+///     "00000000  48 65 6c 6c                                       |Hell|\n\
+///      ..\n\
+///      00000012",
+/// );
+/// ```
+macro_rules! hexdump {
+    ($array:expr) => {
+        $crate::hex::HexDump($array)
+    };
+}
+
+const HEXDUMP_ROW: usize = 16;
+
+fn hexdump_rows(
+    bytes: &[u8],
+    offset: usize,
+    f: &mut fmt::Formatter<'_>,
+    upper: bool,
+) -> Result<(), fmt::Error> {
+    for (i, row) in bytes.chunks(HEXDUMP_ROW).enumerate() {
+        let offset = offset + i * HEXDUMP_ROW;
+        if upper {
+            write!(f, "{:08X} ", offset)?;
+        } else {
+            write!(f, "{:08x} ", offset)?;
+        }
+        for column in 0..HEXDUMP_ROW {
+            if column % 8 == 0 {
+                f.write_str(" ")?;
+            }
+            match row.get(column) {
+                Some(byte) if upper => write!(f, "{:02X} ", byte)?,
+                Some(byte) => write!(f, "{:02x} ", byte)?,
+                None => f.write_str("   ")?,
+            }
+        }
+        f.write_str(" |")?;
+        for &byte in row {
+            let printable = byte.is_ascii_graphic() || byte == b' ';
+            fmt::Write::write_char(f, if printable { byte as char } else { '.' })?;
+        }
+        f.write_str("|\n")?;
+    }
+    Ok(())
+}
+
+fn hexdump(bytes: &[u8], f: &mut fmt::Formatter<'_>, upper: bool) -> Result<(), fmt::Error> {
+    use core::fmt::Alignment::*;
+    let max_bytes = f.width().unwrap_or(usize::MAX);
+
+    if bytes.len() <= max_bytes {
+        hexdump_rows(bytes, 0, f, upper)?;
+    } else {
+        let align = f.align().unwrap_or(Center);
+        let (left, right) = match align {
+            Left => (max_bytes, 0),
+            Center => (max_bytes - max_bytes / 2, max_bytes / 2),
+            Right => (0, max_bytes),
+        };
+        hexdump_rows(&bytes[..left], 0, f, upper)?;
+        f.write_str("..\n")?;
+        let offset = bytes.len() - right;
+        hexdump_rows(&bytes[offset..], offset, f, upper)?;
+    }
+
+    if upper {
+        write!(f, "{:08X}", bytes.len())
+    } else {
+        write!(f, "{:08x}", bytes.len())
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::Debug for HexDump<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::Display for HexDump<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::LowerHex for HexDump<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        hexdump(self.0.as_ref(), f, false)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::UpperHex for HexDump<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        hexdump(self.0.as_ref(), f, true)
+    }
+}
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::hex_diff;
 
     #[test]
     fn test_hex_str() {
@@ -300,11 +476,111 @@ mod test {
         insta::assert_debug_snapshot!(format_args!("'{}'", hex_str!(&buf, 4)));
     }
 
+    #[test]
+    fn test_hexdump() {
+        let buf: [u8; 40] = core::array::from_fn(|i| (i * 7) as u8);
+        insta::assert_snapshot!(format!("{}", hexdump!(&buf)));
+        insta::assert_snapshot!(format!("{:X}", hexdump!(&buf)));
+        insta::assert_snapshot!(format!("{:20}", hexdump!(&buf)));
+        insta::assert_snapshot!(format!("{:>20}", hexdump!(&buf)));
+    }
+
+    #[test]
+    fn test_hex_diff() {
+        let left: [u8; 24] = core::array::from_fn(|i| i as u8);
+        let mut right = left;
+        right[0] = 0xFF;
+        right[9] = 0xFF;
+        right[12] = 0xFF;
+        insta::assert_snapshot!(format!("{}", hex_diff!(&left, &right)));
+        insta::assert_snapshot!(format!("{}", hex_diff!(&left, &right, 4).context(1)));
+        insta::assert_snapshot!(format!("{:x}", hex_diff!(&left, &right[..20]).highlight()));
+        assert_eq!(format!("{}", hex_diff!(&left, &left)), "");
+    }
+
+    #[test]
+    fn test_truncation() {
+        let data: [u8; 10] = core::array::from_fn(|i| i as u8);
+        let truncations = [
+            ("default", Truncation::new()),
+            ("count", Truncation::new().count()),
+            ("separated", Truncation::new().separated()),
+            ("total", Truncation::new().total()),
+            (
+                "all",
+                Truncation::new().marker("…").count().separated().total(),
+            ),
+        ];
+        // one line per truncation
+        let lines = |format: &dyn Fn(&dyn fmt::Display) -> String| -> String {
+            truncations
+                .iter()
+                .map(|(name, truncation)| {
+                    let hex_str = hex_str!(&data, 2).truncation(*truncation);
+                    format!("{}: '{}'\n", name, format(&hex_str))
+                })
+                .collect()
+        };
+        insta::assert_snapshot!(lines(&|h| format!("{:<3}", h)));
+        insta::assert_snapshot!(lines(&|h| format!("{:^3}", h)));
+        insta::assert_snapshot!(lines(&|h| format!("{:>3}", h)));
+        insta::assert_snapshot!(lines(&|h| format!("{:^w$}", h, w = 0)));
+        insta::assert_snapshot!(lines(&|h| format!("{}", h)));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_round_trip(
+            data in proptest::collection::vec(proptest::num::u8::ANY, 0..48),
+            separator in "([^0-9a-fA-F]|[ .…]){0,3}",
+        ) {
+            let separator: &'static str = separator.leak();
+            let mut buf = [0u8; 48];
+            // see `decode` and `decode_blocks` for these limits
+            let inferable = !separator.contains("..") && !separator.contains('…');
+            let marker = separator == ".." || separator == "…";
+
+            macro_rules! round_trip {
+                ($n:literal) => {
+                    let hex_str = HexStr::<_, $n>::new(&data[..]).separator(separator);
+                    for formatted in [format!("{:x}", hex_str), format!("{:X}", hex_str)] {
+                        if inferable {
+                            proptest::prop_assert_eq!(decode(&formatted, &mut buf), Ok(&data[..]));
+                        }
+                        proptest::prop_assert_eq!(
+                            decode_blocks(&formatted, $n, separator, &mut buf),
+                            Ok(&data[..])
+                        );
+                    }
+                    if data.len() > 1 {
+                        let truncated = format!("{:1$}", hex_str, data.len() - 1);
+                        proptest::prop_assert_eq!(
+                            decode(&truncated, &mut buf),
+                            Err(ParseError::Truncated)
+                        );
+                        if !marker {
+                            proptest::prop_assert_eq!(
+                                decode_blocks(&truncated, $n, separator, &mut buf),
+                                Err(ParseError::Truncated)
+                            );
+                        }
+                    }
+                };
+            }
+
+            round_trip!(1);
+            round_trip!(2);
+            round_trip!(3);
+            round_trip!(8);
+        }
+    }
+
     #[test]
     fn test_custom_hex_str() {
         let buf = [1u8, 2, 3, 0xA1, 0xB7, 0xFF, 0x3];
-        typeint!(U3, 3);
-        typesep!(Space, " ");
-        insta::assert_debug_snapshot!(format_args!("'{:X}'", HexStr::<_, U3, Space>(&buf),));
+        insta::assert_debug_snapshot!(format_args!(
+            "'{:X}'",
+            HexStr::<_, 3>::new(&buf).separator(" "),
+        ));
     }
 }
@@ -0,0 +1,87 @@
+//! Byte-string literal formatting of mostly textual data.
+
+use core::fmt::{self, Write};
+
+use super::LOWER_DIGITS;
+
+/// Wrapper newtype, formatting bytes like a Rust byte-string literal.
+///
+/// Use the `byte_str!` macro to construct this.
+pub struct ByteStr<'a, T: ?Sized>(pub &'a T);
+
+#[macro_export]
+/// Format byte arrays and slices like a Rust byte-string literal.
+///
+/// Printable ASCII is shown as is, tabs, carriage returns and newlines by their escapes,
+/// and all other bytes as `\x..`. Width and alignment count bytes and are honoured like
+/// for `hex_str!`, with the shown parts quoted separately around the `..`.
+///
+/// ```
+/// use delog::byte_str;
+/// let data = b"GET /\r\n\x00\xff";
+/// assert_eq!(format!("{}", byte_str!(data)), r#"b"GET /\r\n\x00\xff""#);
+/// assert_eq!(format!("{:4}", byte_str!(data)), r#"b"GE"..b"\x00\xff""#);
+/// assert_eq!(format!("{:<3}", byte_str!(b"say \"hi\"")), r#"b"say".."#);
+/// assert_eq!(format!("{:>2}", byte_str!(b"C:\\")), r#"..b":\\""#);
+/// ```
+macro_rules! byte_str {
+    ($array:expr) => {
+        $crate::hex::ByteStr($array)
+    };
+}
+
+fn quoted(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    f.write_str("b\"")?;
+    for &byte in bytes {
+        match byte {
+            b'\t' => f.write_str("\\t")?,
+            b'\r' => f.write_str("\\r")?,
+            b'\n' => f.write_str("\\n")?,
+            b'"' => f.write_str("\\\"")?,
+            b'\\' => f.write_str("\\\\")?,
+            b' '..=b'~' => f.write_char(byte as char)?,
+            _ => {
+                f.write_str("\\x")?;
+                f.write_char(LOWER_DIGITS[(byte >> 4) as usize] as char)?;
+                f.write_char(LOWER_DIGITS[(byte & 0xf) as usize] as char)?;
+            }
+        }
+    }
+    f.write_char('"')
+}
+
+fn byte_str(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    use core::fmt::Alignment::*;
+    let max_bytes = f.width().unwrap_or(usize::MAX);
+
+    if bytes.len() <= max_bytes {
+        quoted(bytes, f)
+    } else {
+        let (left, right) = match f.align().unwrap_or(Center) {
+            Left => (max_bytes, 0),
+            Center => (max_bytes - max_bytes / 2, max_bytes / 2),
+            Right => (0, max_bytes),
+        };
+        if left > 0 {
+            quoted(&bytes[..left], f)?;
+        }
+        f.write_str("..")?;
+        if right > 0 {
+            quoted(&bytes[bytes.len() - right..], f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::Debug for ByteStr<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>> fmt::Display for ByteStr<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        byte_str(self.0.as_ref(), f)
+    }
+}
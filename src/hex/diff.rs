@@ -0,0 +1,262 @@
+//! Hexadecimal diffs of byte arrays and slices.
+
+use core::fmt::{self, Write};
+
+use super::{LOWER_DIGITS, UPPER_DIGITS};
+
+/// Wrapper newtype, formatting the differences of two byte arrays or slices.
+///
+/// Use the `hex_diff!` macro to construct this.
+pub struct HexDiff<'a, T: ?Sized, U: ?Sized, const N: usize = 1> {
+    /// The expected value.
+    pub left: &'a T,
+    /// The actual value.
+    pub right: &'a U,
+    separator: &'static str,
+    context: usize,
+    highlight: bool,
+}
+
+impl<'a, T: ?Sized, U: ?Sized, const N: usize> HexDiff<'a, T, U, N> {
+    /// Compare `left` and `right`, separating blocks by spaces, with two bytes of context.
+    pub const fn new(left: &'a T, right: &'a U) -> Self {
+        Self {
+            left,
+            right,
+            separator: " ",
+            context: 2,
+            highlight: false,
+        }
+    }
+
+    /// Set the separator between blocks.
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Set the number of equal bytes to show around differences.
+    pub const fn context(mut self, context: usize) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Highlight differing bytes with ANSI colors, instead of marking them on an extra line.
+    pub const fn highlight(mut self) -> Self {
+        self.highlight = true;
+        self
+    }
+}
+
+#[macro_export]
+/// Format the differing regions of two byte arrays or slices as hexadecimals.
+///
+/// Each region starts with its offset, followed by a `-` line for the first and a `+` line
+/// for the second argument, and a line marking the differing bytes. Regions closer than
+/// twice the context are merged. Equal arguments format as empty string.
+///
+/// Block size and separator are passed like for `hex_str!`.
+///
+/// ```
+/// use delog::hex_diff;
+/// let expected = [1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+/// let actual = [1u8, 2, 3, 4, 5, 0xFF, 7, 8, 9, 10, 11, 12, 13];
+/// assert_eq!(
+///     format!("{}", hex_diff!(&expected, &actual)),
+///     "@00000003 -04 05 06 07 08\n\
+///      @00000003 +04 05 FF 07 08\n\
+///      \x20                ^^\n\
+///      @0000000A -0B 0C\n\
+///      @0000000A +0B 0C 0D\n\
+///      \x20                ^^",
+/// );
+/// assert_eq!(
+///     format!("{:x}", hex_diff!(&expected, &actual, 2, sep: "|").context(0)),
+///     "@00000004 -0506\n\
+///      @00000004 +05ff\n\
+///      \x20            ^^\n\
+///      @0000000c -\n\
+///      @0000000c +0d\n\
+///      \x20          ^^",
+/// );
+/// ```
+macro_rules! hex_diff {
+    ($left:expr, $right:expr) => { $crate::hex_diff!($left, $right, 1, sep: " ") };
+    ($left:expr, $right:expr, sep: $separator:expr) => { $crate::hex_diff!($left, $right, 1, sep: $separator) };
+    ($left:expr, $right:expr, $n:tt) => { $crate::hex_diff!($left, $right, $n, sep: " ") };
+    ($left:expr, $right:expr, $n:tt, sep: $separator:expr) => {
+        $crate::hex::HexDiff::<_, _, $n>::new($left, $right).separator($separator)
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Left,
+    Right,
+    Marker,
+}
+
+struct Diff<'a> {
+    left: &'a [u8],
+    right: &'a [u8],
+    block: usize,
+    separator: &'a str,
+    highlight: bool,
+    digits: &'a [u8; 16],
+}
+
+impl Diff<'_> {
+    fn differs(&self, i: usize) -> bool {
+        self.left.get(i) != self.right.get(i)
+    }
+
+    fn line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        start: usize,
+        end: usize,
+        line: Line,
+    ) -> fmt::Result {
+        let (sign, bytes, color) = match line {
+            Line::Left => ('-', self.left, "\x1b[31m"),
+            Line::Right => ('+', self.right, "\x1b[32m"),
+            Line::Marker => (' ', &[][..], ""),
+        };
+        if line == Line::Marker {
+            // under the offset
+            f.write_str("          ")?;
+        } else {
+            write_offset(f, start, self.digits)?;
+        }
+        f.write_char(sign)?;
+
+        let end = match line {
+            Line::Marker => end,
+            _ => end.min(bytes.len()),
+        };
+        let mut trailing = 0;
+        for i in start..end {
+            if i > start && (i - start).is_multiple_of(self.block) {
+                match line {
+                    Line::Marker => trailing += self.separator.chars().count(),
+                    _ => f.write_str(self.separator)?,
+                }
+            }
+            let differs = self.differs(i);
+            match line {
+                Line::Marker if differs => {
+                    for _ in 0..trailing {
+                        f.write_char(' ')?;
+                    }
+                    trailing = 0;
+                    f.write_str("^^")?;
+                }
+                Line::Marker => trailing += 2,
+                _ => {
+                    let byte = bytes.get(i).copied().unwrap_or_default();
+                    let highlight = differs && self.highlight;
+                    if highlight {
+                        f.write_str(color)?;
+                    }
+                    f.write_char(self.digits[(byte >> 4) as usize] as char)?;
+                    f.write_char(self.digits[(byte & 0xf) as usize] as char)?;
+                    if highlight {
+                        f.write_str("\x1b[0m")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_offset(f: &mut fmt::Formatter<'_>, offset: usize, digits: &[u8; 16]) -> fmt::Result {
+    f.write_char('@')?;
+    for shift in (0..8).rev() {
+        f.write_char(digits[(offset >> (4 * shift)) & 0xf] as char)?;
+    }
+    f.write_char(' ')
+}
+
+/// The formatting shared by all `HexDiff`.
+fn hex_diff(diff: &Diff, f: &mut fmt::Formatter<'_>, context: usize) -> fmt::Result {
+    let len = diff.left.len().max(diff.right.len());
+    let mut first = true;
+    let mut i = 0;
+    while let Some(next) = (i..len).find(|&i| diff.differs(i)) {
+        // extend while the next difference is close enough to merge
+        let mut last = next;
+        while let Some(further) =
+            (last + 1..len.min(last + 1 + 2 * context + 1)).find(|&i| diff.differs(i))
+        {
+            last = further;
+        }
+
+        // blocks are aligned to the start of the data
+        let start = next.saturating_sub(context) / diff.block * diff.block;
+        let end = len.min(last + 1 + context);
+
+        if !first {
+            f.write_char('\n')?;
+        }
+        first = false;
+        diff.line(f, start, end, Line::Left)?;
+        f.write_char('\n')?;
+        diff.line(f, start, end, Line::Right)?;
+        if !diff.highlight {
+            f.write_char('\n')?;
+            diff.line(f, start, end, Line::Marker)?;
+        }
+
+        i = end;
+    }
+    Ok(())
+}
+
+impl<T: ?Sized, U: ?Sized, const N: usize> fmt::Debug for HexDiff<'_, T, U, N>
+where
+    T: AsRef<[u8]>,
+    U: AsRef<[u8]>,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, const N: usize> fmt::Display for HexDiff<'_, T, U, N>
+where
+    T: AsRef<[u8]>,
+    U: AsRef<[u8]>,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::UpperHex::fmt(self, f)
+    }
+}
+
+macro_rules! implement {
+    ($Trait:ident, $digits:expr) => {
+        impl<T: ?Sized, U: ?Sized, const N: usize> fmt::$Trait for HexDiff<'_, T, U, N>
+        where
+            T: AsRef<[u8]>,
+            U: AsRef<[u8]>,
+        {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+                let diff = Diff {
+                    left: self.left.as_ref(),
+                    right: self.right.as_ref(),
+                    block: N.max(1),
+                    separator: self.separator,
+                    highlight: self.highlight,
+                    digits: $digits,
+                };
+                hex_diff(&diff, f, self.context)
+            }
+        }
+    };
+}
+
+implement!(LowerHex, LOWER_DIGITS);
+implement!(UpperHex, UPPER_DIGITS);
@@ -0,0 +1,187 @@
+//! Parsing of `HexStr` output back into bytes.
+
+use core::{fmt, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Reasons why a string is not the output of `HexStr`.
+pub enum ParseError {
+    /// The string contains the ellipsis of truncated output, so bytes are missing.
+    Truncated,
+    /// The character at this byte offset is neither a hexadecimal digit nor part of a separator.
+    InvalidCharacter(usize),
+    /// A block has an odd number of hexadecimal digits.
+    OddDigits,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Truncated => f.write_str("truncated hex string, elided bytes are lost"),
+            ParseError::InvalidCharacter(offset) => write!(f, "invalid character at {}", offset),
+            ParseError::OddDigits => f.write_str("odd number of hex digits in block"),
+            ParseError::BufferTooSmall => f.write_str("buffer too small for decoded bytes"),
+        }
+    }
+}
+
+/// Whether `s` starts with one of the default truncation markers, `..` or `…`.
+fn is_marker(s: &str) -> bool {
+    s.starts_with("..") || s.starts_with('…')
+}
+
+fn nibble(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode `HexStr` output into `buf`, returning the decoded bytes.
+///
+/// The block size and separator are taken from the first block, so this handles all
+/// outputs of `hex_str!` and `hexstr!`, unless the separator contains hexadecimal
+/// digits or a truncation marker (use `decode_blocks` for these). A separator that seems
+/// to contain a marker is taken as truncation.
+///
+/// ```
+/// use delog::{hex::{decode, ParseError}, hex_str};
+///
+/// let mut buf = [0u8; 8];
+/// assert_eq!(decode("07a1 ffc7", &mut buf), Ok(&[7u8, 0xA1, 0xFF, 0xC7][..]));
+/// assert_eq!(decode("07|A1|FF", &mut buf), Ok(&[7u8, 0xA1, 0xFF][..]));
+///
+/// let data = [1u8, 2, 3, 4, 5];
+/// let truncated = format!("{:2}", hex_str!(&data));
+/// assert_eq!(decode(&truncated, &mut buf), Err(ParseError::Truncated));
+/// ```
+pub fn decode<'a>(s: &str, buf: &'a mut [u8]) -> Result<&'a [u8], ParseError> {
+    let digits = s
+        .bytes()
+        .position(|c| nibble(c).is_none())
+        .unwrap_or(s.len());
+    let rest = &s[digits..];
+    let separator = rest
+        .bytes()
+        .position(|c| nibble(c).is_some())
+        .unwrap_or(rest.len());
+    let separator = &rest[..separator];
+    if separator.contains("..") || separator.contains('…') {
+        return Err(ParseError::Truncated);
+    }
+    if digits % 2 != 0 {
+        return Err(ParseError::OddDigits);
+    }
+    decode_blocks(s, digits / 2, separator, buf)
+}
+
+/// Decode `HexStr` output with given block size and separator into `buf`,
+/// returning the decoded bytes.
+///
+/// Truncation markers are recognized in place of blocks and separators. If the separator is
+/// a marker itself, output truncated between full blocks is indistinguishable from complete
+/// output, and decoded as such.
+///
+/// ```
+/// use delog::hex::{decode_blocks, ParseError};
+///
+/// let mut buf = [0u8; 8];
+/// assert_eq!(decode_blocks("07A1-a-FFC7", 2, "-a-", &mut buf), Ok(&[7u8, 0xA1, 0xFF, 0xC7][..]));
+/// assert_eq!(decode_blocks("07 .. A1", 1, " .. ", &mut buf), Ok(&[7u8, 0xA1][..]));
+/// assert_eq!(decode_blocks("07 .. A1..C7", 1, " .. ", &mut buf), Err(ParseError::Truncated));
+/// ```
+pub fn decode_blocks<'a>(
+    s: &str,
+    block_size: usize,
+    separator: &str,
+    buf: &'a mut [u8],
+) -> Result<&'a [u8], ParseError> {
+    let max_digits = 2 * block_size.max(1);
+    let mut used = 0;
+    let mut offset = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        // a marker may follow blocks, with or without separator
+        let before = rest;
+        if offset > 0 {
+            rest = match rest.strip_prefix(separator) {
+                Some(rest) => rest,
+                None if is_marker(before) => return Err(ParseError::Truncated),
+                None => return Err(ParseError::InvalidCharacter(offset)),
+            };
+            offset += separator.len();
+        }
+
+        let digits = rest
+            .bytes()
+            .take(max_digits)
+            .take_while(|&c| nibble(c).is_some())
+            .count();
+        if digits == 0 && (is_marker(rest) || is_marker(before)) {
+            return Err(ParseError::Truncated);
+        }
+        // only the last block may be short
+        if digits == 0 || digits < max_digits && digits < rest.len() {
+            if is_marker(&rest[digits..]) {
+                return Err(ParseError::Truncated);
+            }
+            return Err(ParseError::InvalidCharacter(offset + digits));
+        }
+        if digits % 2 != 0 {
+            return Err(ParseError::OddDigits);
+        }
+
+        for pair in rest.as_bytes()[..digits].chunks(2) {
+            let byte = buf.get_mut(used).ok_or(ParseError::BufferTooSmall)?;
+            // we checked these are digits
+            *byte = (nibble(pair[0]).unwrap_or(0) << 4) | nibble(pair[1]).unwrap_or(0);
+            used += 1;
+        }
+
+        offset += digits;
+        rest = &rest[digits..];
+    }
+
+    Ok(&buf[..used])
+}
+
+/// Fixed capacity bytes, parsed from `HexStr` output.
+///
+/// ```
+/// use delog::hex::Decoded;
+///
+/// let decoded: Decoded<16> = "07A1 FFC7".parse().unwrap();
+/// assert_eq!(&*decoded, &[7u8, 0xA1, 0xFF, 0xC7]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decoded<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FromStr for Decoded<N> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; N];
+        let len = decode(s, &mut buf)?.len();
+        Ok(Self { buf, len })
+    }
+}
+
+impl<const N: usize> core::ops::Deref for Decoded<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Decoded<N> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
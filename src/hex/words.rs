@@ -0,0 +1,309 @@
+//! Hexadecimal formatting of words, wider than bytes.
+
+use core::fmt::{self, Write};
+
+use super::{LOWER_DIGITS, UPPER_DIGITS};
+
+/// Unsigned integers that can be formatted by `HexWords`.
+pub trait Word: Copy {
+    /// Number of bytes, which determines the zero-padding.
+    const BYTES: usize;
+    /// The value, for formatting.
+    fn widen(self) -> u128;
+}
+
+macro_rules! word {
+    ($($ty:ty),*) => {$(
+        impl Word for $ty {
+            const BYTES: usize = core::mem::size_of::<$ty>();
+            #[inline]
+            fn widen(self) -> u128 {
+                self as u128
+            }
+        }
+    )*};
+}
+
+word!(u8, u16, u32, u64, u128, usize);
+
+/// Wrapper newtype, formatting slices of unsigned integers as zero-padded hexadecimals,
+/// grouping them in blocks of `N` words.
+///
+/// Use the `hex_words!` macro to construct this.
+pub struct HexWords<'a, W, const N: usize = 1> {
+    /// The words to be formatted.
+    pub words: &'a [W],
+    separator: &'static str,
+}
+
+impl<'a, W: Word, const N: usize> HexWords<'a, W, N> {
+    /// Wrap `words`, separating blocks by spaces.
+    pub const fn new(words: &'a [W]) -> Self {
+        Self {
+            words,
+            separator: " ",
+        }
+    }
+
+    /// Set the separator between blocks.
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Order in which the bytes of a word are stored.
+pub enum Endian {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Wrapper newtype, formatting byte arrays and slices as words of `W` bytes,
+/// stored in given byte order. A trailing partial word is formatted by itself.
+///
+/// Use the `hex_words!` macro with `le:` or `be:` to construct this.
+/// Words are limited to 16 bytes.
+pub struct HexEndian<'a, T: ?Sized, const W: usize> {
+    /// The value to be formatted.
+    pub value: &'a T,
+    endian: Endian,
+    separator: &'static str,
+}
+
+impl<'a, T: ?Sized, const W: usize> HexEndian<'a, T, W> {
+    const VALID: () = assert!(W > 0 && W <= 16, "words must have 1 to 16 bytes");
+
+    /// Wrap `value`, separating words by spaces.
+    pub const fn new(value: &'a T, endian: Endian) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self {
+            value,
+            endian,
+            separator: " ",
+        }
+    }
+
+    /// Set the separator between words.
+    pub const fn separator(mut self, separator: &'static str) -> Self {
+        self.separator = separator;
+        self
+    }
+}
+
+#[macro_export]
+/// Format slices of unsigned integers, or bytes as little or big endian words, as hexadecimals.
+///
+/// For integers, the second parameter is the number of words in a block, like for `hex_str!`.
+/// Each word is zero-padded to its natural width. Width and alignment count words,
+/// and are honoured like for `hex_str!`.
+///
+/// ```
+/// use delog::hex_words;
+/// let registers = [0x1u16, 0xA1B2, 0xFF];
+/// assert_eq!(format!("{}", hex_words!(&registers)), "0001 A1B2 00FF");
+/// assert_eq!(format!("{:x}", hex_words!(&registers, sep: "|")), "0001|a1b2|00ff");
+/// assert_eq!(format!("{}", hex_words!(&registers, 2)), "0001A1B2 00FF");
+/// assert_eq!(format!("{:<2}", hex_words!(&[1u32, 2, 3])), "00000001 00000002..");
+///
+/// let bytes = [0x78u8, 0x56, 0x34, 0x12, 0xEF, 0xBE];
+/// assert_eq!(format!("{}", hex_words!(&bytes, le: 4)), "12345678 BEEF");
+/// assert_eq!(format!("{}", hex_words!(&bytes, be: 2, sep: ",")), "7856,3412,EFBE");
+/// ```
+macro_rules! hex_words {
+    ($array:expr, le: $n:tt) => { $crate::hex_words!($array, le: $n, sep: " ") };
+    ($array:expr, be: $n:tt) => { $crate::hex_words!($array, be: $n, sep: " ") };
+    ($array:expr, le: $n:tt, sep: $separator:expr) => {
+        $crate::hex::HexEndian::<_, $n>::new($array, $crate::hex::Endian::Little)
+            .separator($separator)
+    };
+    ($array:expr, be: $n:tt, sep: $separator:expr) => {
+        $crate::hex::HexEndian::<_, $n>::new($array, $crate::hex::Endian::Big)
+            .separator($separator)
+    };
+    ($words:expr) => { $crate::hex_words!($words, 1, sep: " ") };
+    ($words:expr, sep: $separator:expr) => { $crate::hex_words!($words, 1, sep: $separator) };
+    ($words:expr, $n:tt) => { $crate::hex_words!($words, $n, sep: " ") };
+    ($words:expr, $n:tt, sep: $separator:expr) => {
+        $crate::hex::HexWords::<_, $n>::new($words).separator($separator)
+    };
+}
+
+fn words_fmt(
+    words: core::ops::Range<usize>,
+    word: &dyn Fn(usize) -> (u128, usize),
+    f: &mut fmt::Formatter<'_>,
+    chunk_size: usize,
+    separator: &str,
+    digits: &[u8; 16],
+) -> Result<(), fmt::Error> {
+    let start = words.start;
+    for i in words {
+        if i > start && (i - start).is_multiple_of(chunk_size) {
+            f.write_str(separator)?;
+        }
+        let (value, bytes) = word(i);
+        for nibble in (0..2 * bytes).rev() {
+            f.write_char(digits[(value >> (4 * nibble)) as usize & 0xf] as char)?;
+        }
+    }
+    Ok(())
+}
+
+/// The formatting shared by `HexWords` and `HexEndian`, truncating like `HexStr`.
+fn hex_words(
+    len: usize,
+    word: &dyn Fn(usize) -> (u128, usize),
+    f: &mut fmt::Formatter<'_>,
+    chunk_size: usize,
+    separator: &str,
+    digits: &[u8; 16],
+) -> Result<(), fmt::Error> {
+    use core::fmt::Alignment::*;
+    let max_words = f.width().unwrap_or(usize::MAX);
+    let chunk_size = chunk_size.max(1);
+
+    if len <= max_words {
+        words_fmt(0..len, word, f, chunk_size, separator, digits)
+    } else {
+        let (left, right) = match f.align().unwrap_or(Center) {
+            Left => (max_words, 0),
+            Center => (max_words - max_words / 2, max_words / 2),
+            Right => (0, max_words),
+        };
+        words_fmt(0..left, word, f, chunk_size, separator, digits)?;
+        f.write_str("..")?;
+        words_fmt(len - right..len, word, f, chunk_size, separator, digits)
+    }
+}
+
+impl<W: Word, const N: usize> fmt::Debug for HexWords<'_, W, N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<W: Word, const N: usize> fmt::Display for HexWords<'_, W, N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::UpperHex::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>, const W: usize> fmt::Debug for HexEndian<'_, T, W> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + AsRef<[u8]>, const W: usize> fmt::Display for HexEndian<'_, T, W> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::UpperHex::fmt(self, f)
+    }
+}
+
+macro_rules! implement {
+    ($Trait:ident, $digits:expr) => {
+        impl<W: Word, const N: usize> fmt::$Trait for HexWords<'_, W, N> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+                let word = |i: usize| (self.words[i].widen(), W::BYTES);
+                hex_words(self.words.len(), &word, f, N, self.separator, $digits)
+            }
+        }
+
+        impl<T: ?Sized + AsRef<[u8]>, const W: usize> fmt::$Trait for HexEndian<'_, T, W> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+                let bytes = self.value.as_ref();
+                let endian = self.endian;
+                let word = |i: usize| {
+                    let chunk = &bytes[i * W..bytes.len().min((i + 1) * W)];
+                    let next = |value: u128, &byte: &u8| (value << 8) | byte as u128;
+                    let value = match endian {
+                        Endian::Big => chunk.iter().fold(0, next),
+                        Endian::Little => chunk.iter().rev().fold(0, next),
+                    };
+                    (value, chunk.len())
+                };
+                hex_words(
+                    bytes.len().div_ceil(W),
+                    &word,
+                    f,
+                    1,
+                    self.separator,
+                    $digits,
+                )
+            }
+        }
+    };
+}
+
+implement!(LowerHex, LOWER_DIGITS);
+implement!(UpperHex, UPPER_DIGITS);
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_full_word() {
+        let bytes = [0x78u8, 0x56, 0x34, 0x12];
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, le: 2)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, le: 2)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, le: 2)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, le: 2)));
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, be: 2)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, be: 2)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, be: 2)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, be: 2)));
+    }
+
+    #[test]
+    fn test_partial_word() {
+        let bytes = [0x78u8, 0x56, 0x34, 0x12, 0xEF, 0xBE, 0xAD];
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes[..5], le: 2)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes[..5], le: 2)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes[..5], le: 2)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes[..5], le: 2)));
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes[..5], be: 2)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes[..5], be: 2)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes[..5], be: 2)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes[..5], be: 2)));
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, le: 4)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, le: 4)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, le: 4)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, le: 4)));
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, be: 4)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, be: 4)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, be: 4)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, be: 4)));
+    }
+
+    #[test]
+    fn test_short_word() {
+        let bytes = [0x78u8, 0x56, 0x34, 0x12, 0xEF, 0xBE, 0xAD];
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, le: 8)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, le: 8)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, le: 8)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, le: 8)));
+    }
+
+    #[test]
+    fn test_widest_word() {
+        let bytes: [u8; 17] = core::array::from_fn(|i| 0xE0 + i as u8);
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, le: 16)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, le: 16)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, le: 16)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, le: 16)));
+        insta::assert_snapshot!(format!("{}", hex_words!(&bytes, be: 16)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&bytes, be: 16)));
+        insta::assert_snapshot!(format!("{:<2}", hex_words!(&bytes, be: 16)));
+        insta::assert_snapshot!(format!("{:>1}", hex_words!(&bytes, be: 16)));
+        insta::assert_snapshot!(format!("{:x}", hex_words!(&[u128::MAX, 1])));
+    }
+}
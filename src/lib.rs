@@ -5,9 +5,9 @@
 //!
 //! It has two main goals:
 //! - logs are stored in a circular static memory buffer, so that logging is "zero-cost in the inner
-//! loop" (apart from the formatting), with deferred actual I/O later via flushing.
+//!   loop" (apart from the formatting), with deferred actual I/O later via flushing.
 //! - compile-time log level settings for applications with multiple library components;
-//! inactive log levels of libraries are completely compiled out.
+//!   inactive log levels of libraries are completely compiled out.
 //!
 //! Moreover, setting the kill switch feature flag `knock-it-off`, any and all traces of logging
 //! are removed from the final binary.
@@ -18,10 +18,10 @@
 //!
 //! From `log`, we inherit:
 //! - static global filters, default `LevelFilter::Trace` (i.e., everything), set via `delog` or
-//! `log` feature flags (multiple settings result in the most restrictive filter)
+//!   `log` feature flags (multiple settings result in the most restrictive filter)
 //! - dynamic global filter, initialized in the "init"/"init_default" constructors of the
-//! macro-generated structs implementing our `Delogger` trait. This can be changed by calls to
-//! the global `set_max_level` function in `log`.
+//!   macro-generated structs implementing our `Delogger` trait. This can be changed by calls to
+//!   the global `set_max_level` function in `log`.
 //!
 //! Libraries that use the logging macros from `log` are governed by the more restrictive of these two settings.
 //!
@@ -35,6 +35,9 @@
 //! feature `log-none`, and some intermediate library activates one of the additive `log-*`
 //! features, setting `log-none` completely turns off logging for this library.
 //!
+//! Within a library, levels can also be compiled in for selected modules only, by passing them to
+//! `generate_macros!`, as in `generate_macros!(debug: ["storage"])`.
+//!
 //! ## Background
 //!
 //! Compared to existing approaches such as `ufmt`, `cortex-m-funnel` and `defmt`,
@@ -82,6 +85,37 @@
 //!
 //! The `fallible` and `immediate` features (default on) activate the `try_*!` and `*_now!` macros, respectively.
 //!
+//! The `macros` feature provides the `#[delog::instrument]` attribute, logging entry to and exit
+//! from functions via the macros `generate_macros!()` defines in the calling crate, and gated
+//! accordingly:
+//!
+//! ```ignore
+//! #[delog::instrument(level = debug, hex(key), skip(buf), elapsed)]
+//! fn encrypt(key: &[u8; 4], nonce: u32, buf: &mut [u8]) -> Result<usize, Error> {
+//!     // logs `enter encrypt(key: 0A 0B 0C 0D, nonce: 7)`,
+//!     // then `exit encrypt -> Ok(16) (elapsed: 3)` if a clock is set via `set_clock`
+//! }
+//! ```
+//!
+//! It also provides `#[derive(delog::LogDebug)]`, deriving compact `Debug` implementations:
+//!
+//! ```ignore
+//! #[derive(delog::LogDebug)]
+//! struct Session {
+//!     id: u16,
+//!     #[log(hex, max = 4)]
+//!     challenge: [u8; 32],
+//!     #[log(secret)]
+//!     pin: u32,
+//!     #[log(skip)]
+//!     scratch: [u8; 1024],
+//! }
+//! // formats as `Session { id: 7, challenge: 07 A1..FF C7, pin: <redacted>, .. }`
+//! ```
+//!
+//! The `reveal-secrets` feature formats values wrapped in `Secret` instead of redacting them.
+//! Only enable it in final binaries, for development.
+//!
 //! ## Warning
 //! The current circular buffer implementation (v0.1.0) is definitely unsound on desktop.
 //! For embedded use, atomics are required (so no Cortex-M0/M1, and no plans to support non-atomic
@@ -109,14 +143,32 @@ pub use log::{Level, LevelFilter, Record};
 #[cfg(feature = "example")]
 pub mod example;
 
+pub mod base;
 pub mod hex;
 
 mod logger;
 mod macros;
+pub mod panic;
 pub mod render;
+pub mod secret;
+mod select;
+pub mod throttle;
+pub mod timing;
+pub mod trunc;
 
 pub use logger::{
-    dequeue, enqueue, try_enqueue, Delogger, State, Statistics, TryLog, TryLogWithStatistics,
+    dequeue, dequeue_bytes, enqueue, enqueue_repeats, try_enqueue, Dedup, Delogger, State,
+    Statistics, TryLog, TryLogWithStatistics,
+};
+pub use secret::Secret;
+
+#[cfg(feature = "macros")]
+pub use delog_macros::{instrument, LogDebug};
+
+// WARNING: these are not part of the crate's public API and are subject to change at any time.
+#[doc(hidden)]
+pub use select::{
+    env_enabled as __private_api_env_enabled, module_enabled as __private_api_module_enabled,
 };
 
 /// A way to pass on logs, user supplied.
@@ -128,6 +180,34 @@ pub use logger::{
 pub trait Flusher: core::fmt::Debug + Send {
     /// Implementor must handle passed log `&str` in some hopefully useful way.
     fn flush(&self, logs: &str);
+
+    /// Handle passed raw log bytes, as drained from the circular buffer.
+    ///
+    /// This is what the deferred loggers actually call. The default implementation
+    /// passes on the UTF-8 valid parts to `flush`, skipping any invalid bytes,
+    /// which is what one wants for the textual renderers.
+    ///
+    /// Flushers used with binary renderers such as [`render::CborRenderer`] should
+    /// override this method, and write out the bytes unchanged.
+    fn flush_bytes(&self, mut logs: &[u8]) {
+        while !logs.is_empty() {
+            match core::str::from_utf8(logs) {
+                Ok(valid) => {
+                    self.flush(valid);
+                    return;
+                }
+                Err(error) => {
+                    let (valid, rest) = logs.split_at(error.valid_up_to());
+                    if !valid.is_empty() {
+                        // we just checked this is valid
+                        self.flush(unsafe { core::str::from_utf8_unchecked(valid) });
+                    }
+                    let invalid = error.error_len().unwrap_or(rest.len());
+                    logs = &rest[invalid..];
+                }
+            }
+        }
+    }
 }
 
 /// A way to format logs, user supplied.
@@ -139,15 +219,43 @@ pub trait Renderer: Send + Sync {
 
 static mut LOGGER: Option<&'static dyn logger::TryLogWithStatistics> = None;
 
+static CLOCK: core::sync::atomic::AtomicPtr<()> =
+    core::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
+
+/// Set the clock used for time-based throttling, as in `debug_throttled!`.
+///
+/// The units are up to the application, say milliseconds or cycles.
+///
+/// ```
+/// fn clock() -> u64 { 42 }
+/// delog::set_clock(clock);
+/// assert_eq!(delog::now(), Some(42));
+/// ```
+pub fn set_clock(clock: fn() -> u64) {
+    CLOCK.store(clock as *mut (), core::sync::atomic::Ordering::Release);
+}
+
+/// The current time of the clock set via `set_clock`, if any.
+pub fn now() -> Option<u64> {
+    let clock = CLOCK.load(core::sync::atomic::Ordering::Acquire);
+    if clock.is_null() {
+        return None;
+    }
+    // only ever set from a `fn() -> u64`
+    let clock: fn() -> u64 = unsafe { core::mem::transmute(clock) };
+    Some(clock())
+}
+
 /// Returns a reference to the logger (as `TryLogWithStatistics` implementation)
 pub fn logger() -> &'static mut Option<&'static dyn logger::TryLogWithStatistics> {
-    unsafe { &mut LOGGER }
+    unsafe { &mut *core::ptr::addr_of_mut!(LOGGER) }
 }
 
 // WARNING: this is not part of the crate's public API and is subject to change at any time.
 // Taken from `log` crate, mutatis mutandis.
 // The methods are here and not in `macro` to avoid making the latter public.
 #[doc(hidden)]
+#[allow(clippy::result_unit_err)]
 pub fn __private_api_try_log(
     args: fmt::Arguments,
     level: log::Level,
@@ -167,6 +275,21 @@ pub fn __private_api_try_log(
 
 // WARNING: this is not part of the crate's public API and is subject to change at any time.
 #[doc(hidden)]
+#[cold]
+pub fn __private_api_assert_failed(
+    args: fmt::Arguments,
+    &(module_path, file, line): &(&'static str, &'static str, u32),
+) -> ! {
+    __private_api_try_log(args, log::Level::Error, &("!", module_path, file, line)).ok();
+    if let Some(logger) = *crate::logger() {
+        logger.flush();
+    }
+    panic!("{}", args);
+}
+
+// WARNING: this is not part of the crate's public API and is subject to change at any time.
+#[doc(hidden)]
+#[allow(clippy::result_unit_err)]
 pub fn __private_api_try_log_lit(
     message: &str,
     level: log::Level,
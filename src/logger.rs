@@ -1,4 +1,4 @@
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::{cmp, ptr};
 
 /// Semi-abstract characterization of the deferred loggers that the `delog!` macro produces.
@@ -16,8 +16,12 @@ pub unsafe trait Delogger: log::Log + crate::TryLog + State<&'static AtomicUsize
     fn claimed(&self) -> &'static AtomicUsize;
     /// Call the flusher.
     fn flush(&self, logs: &str);
+    /// Call the flusher with raw bytes.
+    fn flush_bytes(&self, logs: &[u8]);
     /// Actually render the arguments (via internal static buffer).
     fn render(&self, record: &log::Record) -> &'static [u8];
+    /// State of the deduplication of consecutive records.
+    fn dedup(&self) -> &'static Dedup;
 
     /// Capacity of circular buffer.
     fn capacity(&self) -> usize {
@@ -54,6 +58,203 @@ pub struct Statistics {
     pub written: usize,
 }
 
+/// Deduplication of repeated consecutive records, off by default.
+///
+/// When enabled via the generated logger's `set_dedup`, a record that renders to the same
+/// bytes as the previous one is not written to the buffer, but counted. Once a different
+/// record arrives, or on flush, a single `last message repeated N times` record is written
+/// in its place, with the level of the repeated record. Like any other record, it is written
+/// on the logging side (by `try_enqueue` or `enqueue_repeats`), draining stays read-only.
+///
+/// Renderings are compared by hash and length. As rendered bytes are compared, renderers that
+/// include timestamps or similar prevent deduplication. Records rendering to nothing, e.g.
+/// those dropped by a `render::chain::Filter`, are neither counted nor compared against.
+/// If the report does not fit in the buffer, the repeats remain pending, and the record
+/// that would follow it is dropped.
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// static LOGS: Mutex<String> = Mutex::new(String::new());
+///
+/// #[derive(Debug)]
+/// struct Flusher {}
+/// impl delog::Flusher for Flusher {
+///     fn flush(&self, logs: &str) {
+///         LOGS.lock().unwrap().push_str(logs);
+///     }
+/// }
+///
+/// delog::delog!(Delogger, 256, Flusher, renderer: delog::render::DefaultRenderer);
+/// static FLUSHER: Flusher = Flusher {};
+/// Delogger::init(delog::LevelFilter::Info, &FLUSHER, delog::render::default()).ok();
+/// Delogger::set_dedup(true);
+///
+/// for _ in 0..10 {
+///     log::warn!("polling");
+/// }
+/// log::info!("done");
+/// Delogger::flush();
+/// assert_eq!(
+///     *LOGS.lock().unwrap(),
+///     "polling\nlast message repeated 9 times\ndone\n",
+/// );
+/// ```
+///
+/// Filtered records do not interrupt or count as repetitions:
+///
+/// ```
+/// use std::sync::Mutex;
+/// use delog::render::{chain::Filter, Chain, DefaultRenderer};
+///
+/// static LOGS: Mutex<String> = Mutex::new(String::new());
+///
+/// #[derive(Debug)]
+/// struct Flusher {}
+/// impl delog::Flusher for Flusher {
+///     fn flush(&self, logs: &str) {
+///         LOGS.lock().unwrap().push_str(logs);
+///     }
+/// }
+///
+/// type Pipeline = Chain<Filter, DefaultRenderer>;
+/// static RENDERER: Pipeline = Chain::new(
+///     Filter(|record| record.target() != "noisy"),
+///     DefaultRenderer {},
+/// );
+///
+/// delog::delog!(Delogger, 256, Flusher, renderer: Pipeline);
+/// static FLUSHER: Flusher = Flusher {};
+/// Delogger::init(delog::LevelFilter::Info, &FLUSHER, &RENDERER).ok();
+/// Delogger::set_dedup(true);
+///
+/// log::info!("hello");
+/// for _ in 0..5 {
+///     log::info!(target: "noisy", "filtered");
+/// }
+/// log::info!("bye");
+/// log::info!(target: "noisy", "filtered");
+/// log::info!("bye");
+/// Delogger::flush();
+/// assert_eq!(
+///     *LOGS.lock().unwrap(),
+///     "hello\nbye\nlast message repeated 1 time\n",
+/// );
+/// ```
+///
+/// Repeats whose report does not fit are reported later:
+///
+/// ```
+/// use delog::TryLog;
+/// # use std::sync::Mutex;
+/// # static LOGS: Mutex<String> = Mutex::new(String::new());
+/// # #[derive(Debug)]
+/// # struct Flusher {}
+/// # impl delog::Flusher for Flusher {
+/// #     fn flush(&self, logs: &str) {
+/// #         LOGS.lock().unwrap().push_str(logs);
+/// #     }
+/// # }
+/// delog::delog!(Delogger, 40, Flusher, renderer: delog::render::DefaultRenderer);
+/// static FLUSHER: Flusher = Flusher {};
+/// Delogger::init(delog::LevelFilter::Info, &FLUSHER, delog::render::default()).ok();
+/// Delogger::set_dedup(true);
+///
+/// log::info!("{}", "x".repeat(20));
+/// for _ in 0..3 {
+///     log::info!("a");
+/// }
+/// // no space for the report, so neither it nor the record is written
+/// let record = log::Record::builder().args(format_args!("b")).build();
+/// assert!(delog::logger().unwrap().try_log(&record).is_err());
+/// Delogger::flush();
+/// log::info!("c");
+/// Delogger::flush();
+/// assert_eq!(
+///     *LOGS.lock().unwrap(),
+///     format!("{}\na\nlast message repeated 2 times\nc\n", "x".repeat(20)),
+/// );
+/// ```
+pub struct Dedup {
+    enabled: AtomicBool,
+    // hash and length of the previous record's rendering, zero length if none
+    last: AtomicUsize,
+    last_len: AtomicUsize,
+    level: AtomicUsize,
+    repeats: AtomicUsize,
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dedup {
+    /// New, disabled deduplication state.
+    pub const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            last: AtomicUsize::new(0),
+            last_len: AtomicUsize::new(0),
+            level: AtomicUsize::new(0),
+            repeats: AtomicUsize::new(0),
+        }
+    }
+
+    /// Turn deduplication on or off.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Hash and length of `log`, if deduplication is enabled and it is not empty.
+    fn fingerprint(&self, log: &[u8]) -> Option<(usize, usize)> {
+        if !self.enabled.load(Ordering::SeqCst) || log.is_empty() {
+            return None;
+        }
+        // FNV-1a, folded to fit atomics of targets without 64 bit ones
+        let hash = log.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Some(((hash ^ (hash >> 32)) as usize, log.len()))
+    }
+
+    /// Whether the `fingerprint` repeats the previous record; if so, it is counted.
+    fn is_repeat(&self, (hash, len): (usize, usize)) -> bool {
+        if self.last.load(Ordering::SeqCst) == hash && self.last_len.load(Ordering::SeqCst) == len {
+            self.repeats.fetch_add(1, Ordering::SeqCst);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remembers the record with `fingerprint` as written.
+    fn set_last(&self, (hash, len): (usize, usize), level: log::Level) {
+        self.last.store(hash, Ordering::SeqCst);
+        self.last_len.store(len, Ordering::SeqCst);
+        self.level.store(level as usize, Ordering::SeqCst);
+    }
+
+    /// Takes the pending number of repeats, with their level.
+    fn take_repeats(&self) -> Option<(usize, log::Level)> {
+        let repeats = self.repeats.swap(0, Ordering::SeqCst);
+        let level = match self.level.load(Ordering::SeqCst) {
+            1 => log::Level::Error,
+            2 => log::Level::Warn,
+            3 => log::Level::Info,
+            4 => log::Level::Debug,
+            _ => log::Level::Trace,
+        };
+        (repeats > 0).then_some((repeats, level))
+    }
+
+    /// Puts back repeats taken by `take_repeats` that could not be reported.
+    fn restore_repeats(&self, repeats: usize) {
+        self.repeats.fetch_add(repeats, Ordering::SeqCst);
+    }
+}
+
 /// Fallible, panic-free version of the `log::Log` trait.
 ///
 /// The intention is actually that implementors of this trait also
@@ -63,6 +264,7 @@ pub struct Statistics {
 /// want to crash.
 pub trait TryLog: log::Log {
     /// Fallible logging call (fails when buffer is full)
+    #[allow(clippy::result_unit_err)]
     fn try_log(&self, _: &log::Record) -> core::result::Result<(), ()>;
 }
 
@@ -171,11 +373,12 @@ macro_rules! delog {
             fn flush(&self) {
                 let mut buf = [0u8; $capacity];
 
-                let logs: &str = unsafe { $crate::dequeue(*self, &mut buf) };
+                unsafe { $crate::enqueue_repeats(*self) };
+                let logs: &[u8] = unsafe { $crate::dequeue_bytes(*self, &mut buf) };
 
                 if logs.len() > 0 {
                     use $crate::Flusher;
-                    self.flusher.flush(logs);
+                    self.flusher.flush_bytes(logs);
                 }
             }
 
@@ -242,7 +445,7 @@ macro_rules! delog {
 
             fn get() -> &'static mut Option<$logger> {
                 static mut LOGGER: Option<$logger> = None;
-                unsafe { &mut LOGGER }
+                unsafe { &mut *core::ptr::addr_of_mut!(LOGGER) }
             }
 
             pub fn flush() {
@@ -251,6 +454,13 @@ macro_rules! delog {
                     $crate::log::Log::flush(logger)
                 }
             }
+
+            /// Turn deduplication of repeated consecutive records on or off, see `delog::Dedup`.
+            pub fn set_dedup(enabled: bool) {
+                if let Some(logger) = Self::get() {
+                    $crate::Delogger::dedup(logger).set_enabled(enabled)
+                }
+            }
         }
 
         impl $crate::State<&'static core::sync::atomic::AtomicUsize> for $logger {
@@ -288,7 +498,7 @@ macro_rules! delog {
         unsafe impl $crate::Delogger for $logger {
             fn buffer(&self) -> &'static mut [u8] {
                 static mut BUFFER: [u8; $capacity] = [0u8; $capacity];
-                unsafe { &mut BUFFER }
+                unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) }
             }
 
             fn flush(&self, logs: &str) {
@@ -296,16 +506,26 @@ macro_rules! delog {
                 self.flusher.flush(logs)
             }
 
+            fn flush_bytes(&self, logs: &[u8]) {
+                use $crate::Flusher;
+                self.flusher.flush_bytes(logs)
+            }
+
             fn claimed(&self) -> &'static core::sync::atomic::AtomicUsize {
                 use core::sync::atomic::AtomicUsize;
                 static CLAIMED: AtomicUsize = AtomicUsize::new(0);
                 &CLAIMED
             }
 
+            fn dedup(&self) -> &'static $crate::Dedup {
+                static DEDUP: $crate::Dedup = $crate::Dedup::new();
+                &DEDUP
+            }
+
             fn render(&self, record: &$crate::Record) -> &'static [u8] {
                 static mut LOCAL_BUFFER: [u8; $render_capacity] = [0u8; $render_capacity];
 
-                let local_buffer = unsafe { &mut LOCAL_BUFFER };
+                let local_buffer = unsafe { &mut *core::ptr::addr_of_mut!(LOCAL_BUFFER) };
                 use $crate::Renderer;
                 self.renderer.render(local_buffer, record)
             }
@@ -402,10 +622,12 @@ macro_rules! delog {
 
             fn get() -> &'static mut Option<$logger> {
                 static mut LOGGER: Option<$logger> = None;
-                unsafe { &mut LOGGER }
+                unsafe { &mut *core::ptr::addr_of_mut!(LOGGER) }
             }
 
             pub fn flush() {}
+
+            pub fn set_dedup(enabled: bool) {}
         }
 
         impl $crate::State<&'static core::sync::atomic::AtomicUsize> for $logger {
@@ -447,12 +669,19 @@ macro_rules! delog {
 
             fn flush(&self, logs: &str) {}
 
+            fn flush_bytes(&self, logs: &[u8]) {}
+
             fn claimed(&self) -> &'static core::sync::atomic::AtomicUsize {
                 use core::sync::atomic::AtomicUsize;
                 static CLAIMED: AtomicUsize = AtomicUsize::new(0);
                 &CLAIMED
             }
 
+            fn dedup(&self) -> &'static $crate::Dedup {
+                static DEDUP: $crate::Dedup = $crate::Dedup::new();
+                &DEDUP
+            }
+
             fn render(&self, record: &$crate::Record) -> &'static [u8] {
                 &[]
             }
@@ -487,7 +716,7 @@ pub unsafe fn enqueue(delogger: impl Delogger, record: &log::Record) {
 /// wraparound. If so, the writer **atomically advances the claim counter**, and starts copying
 /// its data in this newly claimed space. At the end, it is the duty of the "first" caller
 /// to advance the `written` counter to the correct state.
-#[allow(unused_unsafe, unused_variables)]
+#[allow(clippy::result_unit_err, unused_unsafe, unused_variables)]
 pub unsafe fn try_enqueue(
     delogger: impl Delogger,
     record: &log::Record,
@@ -514,79 +743,135 @@ pub unsafe fn try_enqueue(
         if record.target() == "!" {
             // todo: possibly use separate immediate_flusher
             let input = delogger.render(record);
-            let input = unsafe { core::str::from_utf8_unchecked(input) };
-            Delogger::flush(&delogger, input);
+            Delogger::flush_bytes(&delogger, input);
             delogger.successes().fetch_add(1, Ordering::SeqCst);
             return Ok(());
         }
 
-        let capacity = delogger.capacity();
         let log = delogger.render(record);
-        let size = log.len();
+        // records rendering to nothing (e.g. filtered) take no part in deduplication
+        let fingerprint = delogger.dedup().fingerprint(log);
+        let log = match fingerprint {
+            Some(fingerprint) if delogger.dedup().is_repeat(fingerprint) => {
+                delogger.successes().fetch_add(1, Ordering::SeqCst);
+                return Ok(());
+            }
+            Some(_) => match report_repeats(&delogger) {
+                // the report used the render buffer
+                Some(result) => {
+                    result?;
+                    delogger.render(record)
+                }
+                None => log,
+            },
+            None => log,
+        };
 
-        let previously_claimed = loop {
-            let read = delogger.read().load(Ordering::SeqCst);
-            let claimed = delogger.claimed().load(Ordering::SeqCst);
+        write(&delogger, log)?;
+        if let Some(fingerprint) = fingerprint {
+            delogger.dedup().set_last(fingerprint, record.level());
+        }
+        delogger.successes().fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
 
-            // figure out the corner cases for "wrap-around" at usize capacity
-            if claimed + size > read + capacity {
-                // not enough space, currently
-                return Err(());
-            }
+/// Writes a record for pending repeats of deduplicated records, if any, see `Dedup`.
+/// Marked unsafe to discourage use!
+///
+/// # Safety
+/// Unfortunately exposed for all to see, as the `delog!` macro calls it before draining.
+/// Like `try_enqueue`, it renders and writes, so the same considerations apply.
+#[allow(unused_unsafe)]
+pub unsafe fn enqueue_repeats(delogger: impl Delogger) {
+    report_repeats(&delogger);
+}
 
-            // try to stake out our claim
-            let previous = delogger.claimed().compare_exchange(
-                claimed,
-                claimed + size,
-                Ordering::SeqCst,
-                Ordering::SeqCst,
-            );
+/// Writes a record for pending repeats, if any, returning whether it succeeded.
+///
+/// If the write fails, the repeats stay pending.
+fn report_repeats(delogger: &impl Delogger) -> Option<core::result::Result<(), ()>> {
+    let (repeats, level) = delogger.dedup().take_repeats()?;
+    let plural = if repeats == 1 { "" } else { "s" };
+    let log = delogger.render(
+        &log::Record::builder()
+            .args(format_args!(
+                "last message repeated {} time{}",
+                repeats, plural
+            ))
+            .level(level)
+            .target("delog")
+            .build(),
+    );
+    let result = write(delogger, log);
+    if result.is_err() {
+        delogger.dedup().restore_repeats(repeats);
+    }
+    Some(result)
+}
 
-            // we were not interrupted, the region is now ours
-            if previous == Ok(claimed) {
-                break claimed;
-            }
-        };
+/// Copies `log` into the circular buffer, see `try_enqueue`.
+fn write(delogger: &impl Delogger, log: &[u8]) -> core::result::Result<(), ()> {
+    let capacity = delogger.capacity();
+    let size = log.len();
 
-        // find out if we're the "first" and will need to update `written` at the end:
-        let written = delogger.written().load(Ordering::SeqCst);
-        let first: bool = written == previously_claimed;
-
-        // now copy our data - we can be interrupted here at anytime
-        let destination = previously_claimed % capacity;
-        let buffer = delogger.buffer();
-        if destination + size < capacity {
-            // can do a single copy
-            unsafe {
-                ptr::copy_nonoverlapping(log.as_ptr(), buffer.as_mut_ptr().add(destination), size)
-            };
-        } else {
-            // need to split
-            let split = capacity - destination;
-            unsafe {
-                ptr::copy_nonoverlapping(log.as_ptr(), buffer.as_mut_ptr().add(destination), split);
-                ptr::copy_nonoverlapping(
-                    log.as_ptr().add(split),
-                    buffer.as_mut_ptr(),
-                    size - split,
-                );
-            }
+    let previously_claimed = loop {
+        let read = delogger.read().load(Ordering::SeqCst);
+        let claimed = delogger.claimed().load(Ordering::SeqCst);
+
+        // figure out the corner cases for "wrap-around" at usize capacity
+        if claimed + size > read + capacity {
+            // not enough space, currently
+            return Err(());
         }
 
-        if first {
-            // update `written` to current `claimed` (which may be beyond our own claim)
-            loop {
-                let claimed = delogger.claimed().load(Ordering::SeqCst);
-                delogger.written().store(claimed, Ordering::SeqCst);
-                if claimed == delogger.claimed().load(Ordering::SeqCst) {
-                    break;
-                }
-            }
+        // try to stake out our claim
+        let previous = delogger.claimed().compare_exchange(
+            claimed,
+            claimed + size,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+
+        // we were not interrupted, the region is now ours
+        if previous == Ok(claimed) {
+            break claimed;
         }
+    };
 
-        delogger.successes().fetch_add(1, Ordering::SeqCst);
-        Ok(())
+    // find out if we're the "first" and will need to update `written` at the end:
+    let written = delogger.written().load(Ordering::SeqCst);
+    let first: bool = written == previously_claimed;
+
+    // now copy our data - we can be interrupted here at anytime
+    let destination = previously_claimed % capacity;
+    let buffer = delogger.buffer();
+    if destination + size < capacity {
+        // can do a single copy
+        unsafe {
+            ptr::copy_nonoverlapping(log.as_ptr(), buffer.as_mut_ptr().add(destination), size)
+        };
+    } else {
+        // need to split
+        let split = capacity - destination;
+        unsafe {
+            ptr::copy_nonoverlapping(log.as_ptr(), buffer.as_mut_ptr().add(destination), split);
+            ptr::copy_nonoverlapping(log.as_ptr().add(split), buffer.as_mut_ptr(), size - split);
+        }
     }
+
+    if first {
+        // update `written` to current `claimed` (which may be beyond our own claim)
+        loop {
+            let claimed = delogger.claimed().load(Ordering::SeqCst);
+            delogger.written().store(claimed, Ordering::SeqCst);
+            if claimed == delogger.claimed().load(Ordering::SeqCst) {
+                break;
+            }
+        }
+    }
+
+    Ok(())
 }
 
 /// The core "read from circular buffer" method. Marked unsafe to discourage use!
@@ -601,6 +886,17 @@ pub unsafe fn dequeue(delogger: impl Delogger, buf: &mut [u8]) -> &str {
     unsafe { core::str::from_utf8_unchecked(drain_as_bytes(delogger, buf)) }
 }
 
+/// The "read from circular buffer" method for binary renderers. Marked unsafe to discourage use!
+///
+/// # Safety
+/// Unfortunately exposed for all to see, as the `delog!` macro needs access to it to
+/// implement the logger at call site. Hence marked as unsafe.
+#[allow(unused_unsafe)]
+pub unsafe fn dequeue_bytes(delogger: impl Delogger, buf: &mut [u8]) -> &[u8] {
+    delogger.flushes().fetch_add(1, Ordering::SeqCst);
+    drain_as_bytes(delogger, buf)
+}
+
 /// Copy out the contents of the `Logger` ring buffer into the given buffer,
 /// updating `read` to make space for new log data
 fn drain_as_bytes(delogger: impl Delogger, buf: &mut [u8]) -> &[u8] {
@@ -35,6 +35,81 @@ macro_rules! try_log {
     });
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __private_api_level {
+    (error) => {
+        $crate::Level::Error
+    };
+    (warn) => {
+        $crate::Level::Warn
+    };
+    (info) => {
+        $crate::Level::Info
+    };
+    (debug) => {
+        $crate::Level::Debug
+    };
+    (trace) => {
+        $crate::Level::Trace
+    };
+}
+
+// Whether the calling module is selected for the level, via the arguments of `generate_macros!`
+// or `DELOG_LEVELS`, evaluated at compile time.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __private_api_selected {
+    ([$($level:ident: $module:literal),*], $lvl:expr) => {
+        !cfg!(feature = "log-none")
+            && ($crate::__private_api_module_enabled(
+                $lvl,
+                ::core::module_path!(),
+                &[$(($crate::__private_api_level!($level), $module)),*],
+            ) || $crate::__private_api_env_enabled(
+                $lvl,
+                ::core::module_path!(),
+                ::core::option_env!("DELOG_LEVELS"),
+            ))
+    };
+}
+
+// Whether the level is compiled in for the calling module, via the `log-*` features or selection.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __private_api_compiled_in {
+    ([$($selection:tt)*], $lvl:expr) => {
+        !cfg!(feature = "log-none")
+            && match $lvl {
+                $crate::Level::Error => cfg!(any(feature = "log-all", feature = "log-error")),
+                $crate::Level::Warn => cfg!(any(feature = "log-all", feature = "log-warn")),
+                $crate::Level::Info => cfg!(any(feature = "log-all", feature = "log-info")),
+                $crate::Level::Debug => cfg!(any(feature = "log-all", feature = "log-debug")),
+                $crate::Level::Trace => cfg!(any(feature = "log-all", feature = "log-trace")),
+            }
+            || $crate::__private_api_selected!([$($selection)*], $lvl)
+    };
+}
+
+// Logs unless `tick` is `None`, mentioning the number of suppressed calls if any.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __private_api_try_log_suppressed {
+    (target: $target:expr, $tick:expr, $lvl:expr, $($arg:tt)+) => (
+        match $tick {
+            ::core::option::Option::Some(0) => $crate::try_log!(target: $target, $lvl, $($arg)+),
+            ::core::option::Option::Some(suppressed) => $crate::try_log!(
+                target: $target,
+                $lvl,
+                "{} ({} suppressed)",
+                ::core::format_args!($($arg)+),
+                suppressed
+            ),
+            ::core::option::Option::None => ::core::result::Result::<(), ()>::Ok(()),
+        }
+    );
+}
+
 // There is a syntax issue with "repetitions in binding patterns for nested macros",
 // with a workaround: https://github.com/rust-lang/rust/issues/35853#issuecomment-443110660
 //
@@ -56,11 +131,114 @@ macro_rules! with_dollar_sign {
 /// Realize that these macros are generated **in the namespace of the consuming library**, the one
 /// that actally later makes calls to `local_warn!` etc.
 ///
+/// Optionally, levels can be compiled in for selected modules only, independent of the
+/// library's `log-*` features. The modules are given relative to the crate root, and
+/// include their submodules; as for `log::LevelFilter`, a level also enables all more
+/// severe levels. The `log-none` kill-switch still turns off everything.
+///
+/// ```ignore
+/// // in lib.rs: debug logs in `storage`, trace logs in `storage::journal` and `net::tcp`
+/// delog::generate_macros!(debug: ["storage"], trace: ["storage::journal", "net::tcp"]);
+/// ```
+///
+/// Alternatively, without touching any manifests, the final application can select levels
+/// centrally by setting the `DELOG_LEVELS` environment variable during the build, to a
+/// comma-separated list of crates or module paths with their level:
+///
+/// ```text
+/// DELOG_LEVELS=lib_a=info,lib_b::storage=trace cargo build
+/// ```
+///
+/// Cargo tracks the variable, and recompiles the libraries when it changes.
+///
+/// The selection is a constant, so unselected calls are compiled out just as with the features.
+///
+/// To skip expensive preparation of arguments, `log_enabled!(level)` or
+/// `log_enabled!(target: "...", level)` tells whether a level would be logged. Whether it is
+/// compiled in is a constant, also available on its own:
+///
+/// ```
+/// delog::generate_macros!(debug: ["storage"]);
+///
+/// mod storage {
+///     pub const DEBUG: bool = log_enabled!(@compiled_in delog::Level::Debug);
+///     pub const TRACE: bool = log_enabled!(@compiled_in delog::Level::Trace);
+/// }
+///
+/// fn main() {
+///     assert!(storage::DEBUG && !storage::TRACE);
+///     // no logger is set, so nothing would be logged
+///     assert!(!log_enabled!(delog::Level::Info));
+///     assert!(!log_enabled!(target: "!", delog::Level::Info));
+/// }
+/// ```
+///
+/// Besides `info!`, `info_now!` and their fallible `try_` forms (likewise for all levels),
+/// the generated macros include `info_once!(...)`, `info_every_n!(n, ...)` and
+/// `info_throttled!(period, ...)` to keep polling loops from flooding the buffer, all of which
+/// also take a `target: ...` first; see the `throttle` module.
+///
+/// For latency measurements, `time_scope!("name")` logs the time until the end of the enclosing
+/// scope, see the `timing` module.
+///
+/// In place of `debug_assert!` and `debug_assert_eq!`, `log_assert!` and `log_assert_eq!` check
+/// their conditions if the error level is compiled in. On failure, they log the details
+/// immediately and flush the deferred logs before panicking, so the lead-up is not lost.
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// delog::generate_macros!(error: ["driver"]);
+///
+/// mod driver {
+///     pub fn transfer(len: usize) {
+///         info!("compiled out");
+///         error!("starting transfer");
+///         log_assert!(len > 0);
+///         log_assert_eq!(len % 4, 0, "unaligned length {}", len);
+///     }
+/// }
+///
+/// mod untested {
+///     pub fn skip() {
+///         // compiled out, like the logging macros
+///         log_assert!(false);
+///         log_assert_eq!(1, 2);
+///     }
+/// }
+///
+/// static LOGS: Mutex<String> = Mutex::new(String::new());
+///
+/// #[derive(Debug)]
+/// struct Flusher {}
+/// impl delog::Flusher for Flusher {
+///     fn flush(&self, logs: &str) {
+///         LOGS.lock().unwrap().push_str(logs);
+///     }
+/// }
+///
+/// fn main() {
+///     delog::delog!(Delogger, 256, Flusher, renderer: delog::render::DefaultRenderer);
+///     static FLUSHER: Flusher = Flusher {};
+///     Delogger::init(delog::LevelFilter::Trace, &FLUSHER, delog::render::default()).ok();
+///
+///     untested::skip();
+///     driver::transfer(8);
+///     assert!(std::panic::catch_unwind(|| driver::transfer(6)).is_err());
+///     assert_eq!(
+///         *LOGS.lock().unwrap(),
+///         "assertion failed: `(left == right)` (left: `2`, right: `0`): unaligned length 6\n\
+///          starting transfer\n\
+///          starting transfer\n",
+///     );
+/// }
+/// ```
+///
 /// To see this in action, compile documentation using `cargo doc --features example`, or inspect
 /// the `gate-tests/` subdirectory.
 #[macro_export]
 macro_rules! generate_macros {
-    () => {
+    ($($level:ident: [$($module:literal),* $(,)?]),* $(,)?) => {
         $crate::with_dollar_sign! {
             ($d:tt) => {
 
@@ -70,6 +248,8 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_debug {
 
+                    (@if_enabled $body:block) => ($body);
+
                     (target: $target:expr, $d($arg:tt)+) => (
                         $crate::try_log!(target: $target, $crate::Level::Debug, $d($arg)+)
                     );
@@ -85,8 +265,28 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_debug {
 
-                    // (target: $target:expr, $d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
-                    ($d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
+                    (@if_enabled $body:block) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Debug);
+                        if ENABLED $body else { core::result::Result::<(), ()>::Ok(()) }
+                    });
+
+                    (target: $target:expr, $d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Debug);
+                        if ENABLED {
+                            $crate::try_log!(target: $target, $crate::Level::Debug, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+
+                    ($d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Debug);
+                        if ENABLED {
+                            $crate::try_log!($crate::Level::Debug, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
                 }
 
                 /// Fallible version of `error!`.
@@ -95,6 +295,8 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_error {
 
+                    (@if_enabled $body:block) => ($body);
+
                     (target: $target:expr, $d($arg:tt)+) => (
                         $crate::try_log!(target: $target, $crate::Level::Error, $d($arg)+)
                     );
@@ -110,8 +312,28 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_error {
 
-                    // (target: $target:expr, $d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
-                    ($d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
+                    (@if_enabled $body:block) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED $body else { core::result::Result::<(), ()>::Ok(()) }
+                    });
+
+                    (target: $target:expr, $d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED {
+                            $crate::try_log!(target: $target, $crate::Level::Error, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+
+                    ($d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED {
+                            $crate::try_log!($crate::Level::Error, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
                 }
 
                 /// Fallible version of `info!`.
@@ -120,6 +342,8 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_info {
 
+                    (@if_enabled $body:block) => ($body);
+
                     (target: $target:expr, $d($arg:tt)+) => (
                         $crate::try_log!(target: $target, $crate::Level::Info, $d($arg)+)
                     );
@@ -135,8 +359,28 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_info {
 
-                    // (target: $target:expr, $d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
-                    ($d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
+                    (@if_enabled $body:block) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Info);
+                        if ENABLED $body else { core::result::Result::<(), ()>::Ok(()) }
+                    });
+
+                    (target: $target:expr, $d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Info);
+                        if ENABLED {
+                            $crate::try_log!(target: $target, $crate::Level::Info, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+
+                    ($d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Info);
+                        if ENABLED {
+                            $crate::try_log!($crate::Level::Info, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
                 }
 
                 /// Fallible version of `trace!`.
@@ -145,6 +389,8 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_trace {
 
+                    (@if_enabled $body:block) => ($body);
+
                     (target: $target:expr, $d($arg:tt)+) => (
                         $crate::try_log!(target: $target, $crate::Level::Trace, $d($arg)+)
                     );
@@ -160,8 +406,28 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_trace {
 
-                    (target: $target:expr, $d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
-                    ($d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
+                    (@if_enabled $body:block) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Trace);
+                        if ENABLED $body else { core::result::Result::<(), ()>::Ok(()) }
+                    });
+
+                    (target: $target:expr, $d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Trace);
+                        if ENABLED {
+                            $crate::try_log!(target: $target, $crate::Level::Trace, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+
+                    ($d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Trace);
+                        if ENABLED {
+                            $crate::try_log!($crate::Level::Trace, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
                 }
 
                 /// Fallible version of `warn!`.
@@ -170,6 +436,8 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_warn {
 
+                    (@if_enabled $body:block) => ($body);
+
                     (target: $target:expr, $d($arg:tt)+) => (
                         $crate::try_log!(target: $target, $crate::Level::Warn, $d($arg)+)
                     );
@@ -185,8 +453,169 @@ macro_rules! generate_macros {
                 #[doc(hidden)]
                 macro_rules! try_warn {
 
-                    (target: $target:expr, $d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
-                    ($d($arg:tt)+) => ( core::result::Result::<(), ()>::Ok(()) );
+                    (@if_enabled $body:block) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Warn);
+                        if ENABLED $body else { core::result::Result::<(), ()>::Ok(()) }
+                    });
+
+                    (target: $target:expr, $d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Warn);
+                        if ENABLED {
+                            $crate::try_log!(target: $target, $crate::Level::Warn, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+
+                    ($d($arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_selected!([$($($level: $module),*),*], $crate::Level::Warn);
+                        if ENABLED {
+                            $crate::try_log!($crate::Level::Warn, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    });
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Whether logs of the level would be logged, in case preparing arguments is expensive.
+                ///
+                /// Whether the level is compiled in is decided at compile time, for each level;
+                /// if it is, the static and runtime filters of `log` are consulted, as well as the
+                /// logger for the target, which defaults to the module path as for `log::log_enabled!`.
+                /// `log_enabled!(@compiled_in level)` is only the compile-time part, a constant
+                /// expression for constant levels.
+                macro_rules! log_enabled {
+                    (@compiled_in $d lvl:expr) => {
+                        match $d lvl {
+                            $crate::Level::Error => {
+                                const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Error);
+                                ENABLED
+                            }
+                            $crate::Level::Warn => {
+                                const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Warn);
+                                ENABLED
+                            }
+                            $crate::Level::Info => {
+                                const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Info);
+                                ENABLED
+                            }
+                            $crate::Level::Debug => {
+                                const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Debug);
+                                ENABLED
+                            }
+                            $crate::Level::Trace => {
+                                const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Trace);
+                                ENABLED
+                            }
+                        }
+                    };
+                    (target: $d target:expr, $d lvl:expr) => ({
+                        let lvl: $crate::Level = $d lvl;
+                        log_enabled!(@compiled_in lvl)
+                            && lvl <= $crate::log::STATIC_MAX_LEVEL
+                            && lvl <= $crate::log::max_level()
+                            && $crate::log::Log::enabled(
+                                $crate::log::logger(),
+                                &$crate::log::Metadata::builder().level(lvl).target($d target).build(),
+                            )
+                    });
+                    ($d lvl:expr) => (
+                        log_enabled!(target: ::core::module_path!(), $d lvl)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Time the rest of the enclosing scope, logging the elapsed time when it ends,
+                /// at debug level or the given one.
+                ///
+                /// The level must be a constant. Nothing remains if it is compiled out, and
+                /// nothing happens if it is not logged, or no clock is set.
+                macro_rules! time_scope {
+                    ($d lvl:expr, $d name:expr) => {
+                        let _delog_time_scope = {
+                            const LEVEL: $crate::Level = $d lvl;
+                            const ENABLED: bool = log_enabled!(@compiled_in LEVEL);
+                            <$crate::timing::Gate<ENABLED> as $crate::timing::Start>::start(
+                                LEVEL,
+                                $d name,
+                                &(::core::module_path!(), ::core::module_path!(), ::core::file!(), ::core::line!()),
+                            )
+                        };
+                    };
+                    ($d name:expr) => {
+                        time_scope!($crate::Level::Debug, $d name)
+                    };
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Assert a condition if the error level is compiled in, otherwise do nothing.
+                ///
+                /// On failure, the message is logged immediately, the deferred logs are flushed,
+                /// and then it panics.
+                macro_rules! log_assert {
+                    ($d cond:expr $d(,)?) => ({
+                        const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED && !$d cond {
+                            $crate::__private_api_assert_failed(
+                                ::core::format_args!("assertion failed: {}", ::core::stringify!($d cond)),
+                                &(::core::module_path!(), ::core::file!(), ::core::line!()),
+                            );
+                        }
+                    });
+                    ($d cond:expr, $d($d arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED && !$d cond {
+                            $crate::__private_api_assert_failed(
+                                ::core::format_args!($d($d arg)+),
+                                &(::core::module_path!(), ::core::file!(), ::core::line!()),
+                            );
+                        }
+                    });
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Assert equality if the error level is compiled in, otherwise do nothing.
+                ///
+                /// On failure, the values are logged immediately, the deferred logs are flushed,
+                /// and then it panics.
+                macro_rules! log_assert_eq {
+                    ($d left:expr, $d right:expr $d(,)?) => ({
+                        const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED {
+                            match (&$d left, &$d right) {
+                                (left, right) => if *left != *right {
+                                    $crate::__private_api_assert_failed(
+                                        ::core::format_args!(
+                                            "assertion failed: `(left == right)` (left: `{:?}`, right: `{:?}`)",
+                                            left, right,
+                                        ),
+                                        &(::core::module_path!(), ::core::file!(), ::core::line!()),
+                                    );
+                                }
+                            }
+                        }
+                    });
+                    ($d left:expr, $d right:expr, $d($d arg:tt)+) => ({
+                        const ENABLED: bool = $crate::__private_api_compiled_in!([$($($level: $module),*),*], $crate::Level::Error);
+                        if ENABLED {
+                            match (&$d left, &$d right) {
+                                (left, right) => if *left != *right {
+                                    $crate::__private_api_assert_failed(
+                                        ::core::format_args!(
+                                            "assertion failed: `(left == right)` (left: `{:?}`, right: `{:?}`): {}",
+                                            left, right, ::core::format_args!($d($d arg)+),
+                                        ),
+                                        &(::core::module_path!(), ::core::file!(), ::core::line!()),
+                                    );
+                                }
+                            }
+                        }
+                    });
                 }
 
                 #[cfg(not(feature = "log-none"))]
@@ -295,7 +724,390 @@ macro_rules! generate_macros {
                 /// Fallible immediate version of `warn!`.
                 macro_rules! try_warn_now { ($d($arg:tt)+) => ( try_warn!(target: "!", $d($arg)+) ); }
 
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `debug!`, logging only the first call.
+                macro_rules! try_debug_once {
+                    (target: $target:expr, $d($arg:tt)+) => ( try_debug!(@if_enabled {
+                        static ONCE: $crate::throttle::Once = $crate::throttle::Once::new();
+                        if ONCE.first() {
+                            $crate::try_log!(target: $target, $crate::Level::Debug, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    }) );
+                    ($d($arg:tt)+) => ( try_debug_once!(target: ::core::module_path!(), $d($arg)+) );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `debug!`, logging every `n`-th call.
+                macro_rules! try_debug_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => ( try_debug!(@if_enabled {
+                        static EVERY_N: $crate::throttle::EveryN = $crate::throttle::EveryN::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, EVERY_N.tick($n), $crate::Level::Debug, $d($arg)+
+                        )
+                    }) );
+                    ($n:expr, $d($arg:tt)+) => (
+                        try_debug_every_n!(target: ::core::module_path!(), $n, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `debug!`, logging at most once per `period` of the clock.
+                macro_rules! try_debug_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => ( try_debug!(@if_enabled {
+                        static THROTTLE: $crate::throttle::Throttle = $crate::throttle::Throttle::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, THROTTLE.tick($period), $crate::Level::Debug, $d($arg)+
+                        )
+                    }) );
+                    ($period:expr, $d($arg:tt)+) => (
+                        try_debug_throttled!(target: ::core::module_path!(), $period, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `error!`, logging only the first call.
+                macro_rules! try_error_once {
+                    (target: $target:expr, $d($arg:tt)+) => ( try_error!(@if_enabled {
+                        static ONCE: $crate::throttle::Once = $crate::throttle::Once::new();
+                        if ONCE.first() {
+                            $crate::try_log!(target: $target, $crate::Level::Error, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    }) );
+                    ($d($arg:tt)+) => ( try_error_once!(target: ::core::module_path!(), $d($arg)+) );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `error!`, logging every `n`-th call.
+                macro_rules! try_error_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => ( try_error!(@if_enabled {
+                        static EVERY_N: $crate::throttle::EveryN = $crate::throttle::EveryN::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, EVERY_N.tick($n), $crate::Level::Error, $d($arg)+
+                        )
+                    }) );
+                    ($n:expr, $d($arg:tt)+) => (
+                        try_error_every_n!(target: ::core::module_path!(), $n, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `error!`, logging at most once per `period` of the clock.
+                macro_rules! try_error_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => ( try_error!(@if_enabled {
+                        static THROTTLE: $crate::throttle::Throttle = $crate::throttle::Throttle::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, THROTTLE.tick($period), $crate::Level::Error, $d($arg)+
+                        )
+                    }) );
+                    ($period:expr, $d($arg:tt)+) => (
+                        try_error_throttled!(target: ::core::module_path!(), $period, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `info!`, logging only the first call.
+                macro_rules! try_info_once {
+                    (target: $target:expr, $d($arg:tt)+) => ( try_info!(@if_enabled {
+                        static ONCE: $crate::throttle::Once = $crate::throttle::Once::new();
+                        if ONCE.first() {
+                            $crate::try_log!(target: $target, $crate::Level::Info, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    }) );
+                    ($d($arg:tt)+) => ( try_info_once!(target: ::core::module_path!(), $d($arg)+) );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `info!`, logging every `n`-th call.
+                macro_rules! try_info_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => ( try_info!(@if_enabled {
+                        static EVERY_N: $crate::throttle::EveryN = $crate::throttle::EveryN::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, EVERY_N.tick($n), $crate::Level::Info, $d($arg)+
+                        )
+                    }) );
+                    ($n:expr, $d($arg:tt)+) => (
+                        try_info_every_n!(target: ::core::module_path!(), $n, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `info!`, logging at most once per `period` of the clock.
+                macro_rules! try_info_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => ( try_info!(@if_enabled {
+                        static THROTTLE: $crate::throttle::Throttle = $crate::throttle::Throttle::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, THROTTLE.tick($period), $crate::Level::Info, $d($arg)+
+                        )
+                    }) );
+                    ($period:expr, $d($arg:tt)+) => (
+                        try_info_throttled!(target: ::core::module_path!(), $period, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `trace!`, logging only the first call.
+                macro_rules! try_trace_once {
+                    (target: $target:expr, $d($arg:tt)+) => ( try_trace!(@if_enabled {
+                        static ONCE: $crate::throttle::Once = $crate::throttle::Once::new();
+                        if ONCE.first() {
+                            $crate::try_log!(target: $target, $crate::Level::Trace, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    }) );
+                    ($d($arg:tt)+) => ( try_trace_once!(target: ::core::module_path!(), $d($arg)+) );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `trace!`, logging every `n`-th call.
+                macro_rules! try_trace_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => ( try_trace!(@if_enabled {
+                        static EVERY_N: $crate::throttle::EveryN = $crate::throttle::EveryN::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, EVERY_N.tick($n), $crate::Level::Trace, $d($arg)+
+                        )
+                    }) );
+                    ($n:expr, $d($arg:tt)+) => (
+                        try_trace_every_n!(target: ::core::module_path!(), $n, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `trace!`, logging at most once per `period` of the clock.
+                macro_rules! try_trace_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => ( try_trace!(@if_enabled {
+                        static THROTTLE: $crate::throttle::Throttle = $crate::throttle::Throttle::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, THROTTLE.tick($period), $crate::Level::Trace, $d($arg)+
+                        )
+                    }) );
+                    ($period:expr, $d($arg:tt)+) => (
+                        try_trace_throttled!(target: ::core::module_path!(), $period, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `warn!`, logging only the first call.
+                macro_rules! try_warn_once {
+                    (target: $target:expr, $d($arg:tt)+) => ( try_warn!(@if_enabled {
+                        static ONCE: $crate::throttle::Once = $crate::throttle::Once::new();
+                        if ONCE.first() {
+                            $crate::try_log!(target: $target, $crate::Level::Warn, $d($arg)+)
+                        } else {
+                            core::result::Result::<(), ()>::Ok(())
+                        }
+                    }) );
+                    ($d($arg:tt)+) => ( try_warn_once!(target: ::core::module_path!(), $d($arg)+) );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `warn!`, logging every `n`-th call.
+                macro_rules! try_warn_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => ( try_warn!(@if_enabled {
+                        static EVERY_N: $crate::throttle::EveryN = $crate::throttle::EveryN::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, EVERY_N.tick($n), $crate::Level::Warn, $d($arg)+
+                        )
+                    }) );
+                    ($n:expr, $d($arg:tt)+) => (
+                        try_warn_every_n!(target: ::core::module_path!(), $n, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Fallible version of `warn!`, logging at most once per `period` of the clock.
+                macro_rules! try_warn_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => ( try_warn!(@if_enabled {
+                        static THROTTLE: $crate::throttle::Throttle = $crate::throttle::Throttle::new();
+                        $crate::__private_api_try_log_suppressed!(
+                            target: $target, THROTTLE.tick($period), $crate::Level::Warn, $d($arg)+
+                        )
+                    }) );
+                    ($period:expr, $d($arg:tt)+) => (
+                        try_warn_throttled!(target: ::core::module_path!(), $period, $d($arg)+)
+                    );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `debug!`, logging only the first call.
+                macro_rules! debug_once { ($d($arg:tt)+) => ( try_debug_once!($d($arg)+).ok() ); }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `debug!`, logging every `n`-th call.
+                macro_rules! debug_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => (
+                        try_debug_every_n!(target: $target, $n, $d($arg)+).ok()
+                    );
+                    ($n:expr, $d($arg:tt)+) => ( try_debug_every_n!($n, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `debug!`, logging at most once per `period` of the clock.
+                macro_rules! debug_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => (
+                        try_debug_throttled!(target: $target, $period, $d($arg)+).ok()
+                    );
+                    ($period:expr, $d($arg:tt)+) => ( try_debug_throttled!($period, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `error!`, logging only the first call.
+                macro_rules! error_once { ($d($arg:tt)+) => ( try_error_once!($d($arg)+).ok() ); }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `error!`, logging every `n`-th call.
+                macro_rules! error_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => (
+                        try_error_every_n!(target: $target, $n, $d($arg)+).ok()
+                    );
+                    ($n:expr, $d($arg:tt)+) => ( try_error_every_n!($n, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `error!`, logging at most once per `period` of the clock.
+                macro_rules! error_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => (
+                        try_error_throttled!(target: $target, $period, $d($arg)+).ok()
+                    );
+                    ($period:expr, $d($arg:tt)+) => ( try_error_throttled!($period, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `info!`, logging only the first call.
+                macro_rules! info_once { ($d($arg:tt)+) => ( try_info_once!($d($arg)+).ok() ); }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `info!`, logging every `n`-th call.
+                macro_rules! info_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => (
+                        try_info_every_n!(target: $target, $n, $d($arg)+).ok()
+                    );
+                    ($n:expr, $d($arg:tt)+) => ( try_info_every_n!($n, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `info!`, logging at most once per `period` of the clock.
+                macro_rules! info_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => (
+                        try_info_throttled!(target: $target, $period, $d($arg)+).ok()
+                    );
+                    ($period:expr, $d($arg:tt)+) => ( try_info_throttled!($period, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `trace!`, logging only the first call.
+                macro_rules! trace_once { ($d($arg:tt)+) => ( try_trace_once!($d($arg)+).ok() ); }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `trace!`, logging every `n`-th call.
+                macro_rules! trace_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => (
+                        try_trace_every_n!(target: $target, $n, $d($arg)+).ok()
+                    );
+                    ($n:expr, $d($arg:tt)+) => ( try_trace_every_n!($n, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `trace!`, logging at most once per `period` of the clock.
+                macro_rules! trace_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => (
+                        try_trace_throttled!(target: $target, $period, $d($arg)+).ok()
+                    );
+                    ($period:expr, $d($arg:tt)+) => ( try_trace_throttled!($period, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `warn!`, logging only the first call.
+                macro_rules! warn_once { ($d($arg:tt)+) => ( try_warn_once!($d($arg)+).ok() ); }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `warn!`, logging every `n`-th call.
+                macro_rules! warn_every_n {
+                    (target: $target:expr, $n:expr, $d($arg:tt)+) => (
+                        try_warn_every_n!(target: $target, $n, $d($arg)+).ok()
+                    );
+                    ($n:expr, $d($arg:tt)+) => ( try_warn_every_n!($n, $d($arg)+).ok() );
+                }
+
+                #[macro_use] #[macro_export]
+                #[doc(hidden)]
+                /// Version of `warn!`, logging at most once per `period` of the clock.
+                macro_rules! warn_throttled {
+                    (target: $target:expr, $period:expr, $d($arg:tt)+) => (
+                        try_warn_throttled!(target: $target, $period, $d($arg)+).ok()
+                    );
+                    ($period:expr, $d($arg:tt)+) => ( try_warn_throttled!($period, $d($arg)+).ok() );
+                }
+
             }
         }
     }
 }
+
+#[cfg(test)]
+// the `log-*` features are those of the consuming library, unknown here
+#[allow(unexpected_cfgs)]
+mod test {
+    use crate::Level::*;
+
+    #[test]
+    fn test_selected() {
+        // this module is `delog::macros::test`
+        const DEBUG: bool = crate::__private_api_selected!([debug: "macros"], Debug);
+        const TRACE: bool = crate::__private_api_selected!([debug: "macros"], Trace);
+        const ERROR: bool =
+            crate::__private_api_selected!([error: "select", trace: "macros::test"], Error);
+        const OTHER: bool = crate::__private_api_selected!([trace: "macros::other"], Error);
+        const NONE: bool = crate::__private_api_selected!([], Error);
+        assert_eq!(
+            [DEBUG, TRACE, ERROR, OTHER, NONE],
+            [true, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_compiled_in() {
+        const INFO: bool = crate::__private_api_compiled_in!([info: "macros::test"], Info);
+        const DEBUG: bool = crate::__private_api_compiled_in!([info: "macros::test"], Debug);
+        assert_eq!([INFO, DEBUG], [true, false]);
+        // selected levels only, without `log-*` features
+        for level in [Error, Warn, Info, Debug, Trace] {
+            assert!(!crate::__private_api_compiled_in!([], level));
+        }
+    }
+}
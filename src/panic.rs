@@ -0,0 +1,198 @@
+//! Logging of panics, draining the deferred logs.
+//!
+//! Without further ado, the logs leading up to a panic stay in the buffer, and are lost.
+//! The functions here log the panic through the immediate path, followed by the remaining
+//! deferred logs, via the logger's flusher. Nothing is allocated, and a panic in the flusher or
+//! renderer while doing so (which would recurse) is not logged.
+//!
+//! Under `std`, install the hook:
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! delog::panic::install_hook();
+//! ```
+//!
+//! For `no_std`, call `handle` from the panic handler:
+//!
+//! ```ignore
+//! #[panic_handler]
+//! fn panic(info: &core::panic::PanicInfo) -> ! {
+//!     delog::panic::handle(info);
+//!     loop {}
+//! }
+//! ```
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Log the panic immediately, then flush the deferred logs.
+///
+/// Call this from the `#[panic_handler]`, passing its `PanicInfo`. If called again while
+/// already handling a panic, it returns without logging.
+pub fn handle(info: &dyn fmt::Display) {
+    report(info);
+}
+
+/// Install a panic hook that logs panics like `handle`, and then calls the previous hook.
+#[cfg(any(feature = "std", test))]
+pub fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(std::boxed::Box::new(move |info| {
+        report(info);
+        previous(info);
+    }));
+}
+
+/// Clears `PANICKING` when dropped, also if the flusher or renderer unwinds.
+struct Reset;
+
+impl Drop for Reset {
+    fn drop(&mut self) {
+        PANICKING.store(false, Ordering::SeqCst);
+    }
+}
+
+fn report(info: &dyn fmt::Display) {
+    if PANICKING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _reset = Reset;
+    if let Some(logger) = *crate::logger() {
+        logger
+            .try_log(
+                &log::Record::builder()
+                    .args(format_args!("{}", info))
+                    .level(log::Level::Error)
+                    .target("!")
+                    .module_path_static(Some(module_path!()))
+                    .build(),
+            )
+            .ok();
+        logger.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // not all functions of the generated logger are used
+    #![allow(dead_code)]
+
+    use std::string::String;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    static LOGS: Mutex<String> = Mutex::new(String::new());
+    static PANIC_IN_FLUSHER: AtomicBool = AtomicBool::new(false);
+
+    #[derive(Debug)]
+    pub struct Flusher {}
+    impl crate::Flusher for Flusher {
+        fn flush(&self, logs: &str) {
+            if PANIC_IN_FLUSHER.swap(false, Ordering::SeqCst) {
+                panic!("flusher");
+            }
+            // as if the flusher panicked
+            super::report(&"panicked in flusher");
+            LOGS.lock().unwrap().push_str(logs);
+        }
+    }
+
+    crate::delog!(Delogger, 256, Flusher, renderer: crate::render::DefaultRenderer);
+    static FLUSHER: Flusher = Flusher {};
+
+    // the tests share the logger and the panic hook
+    fn setup() -> MutexGuard<'static, ()> {
+        static SERIAL: Mutex<()> = Mutex::new(());
+        let serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        Delogger::init(
+            crate::LevelFilter::Trace,
+            &FLUSHER,
+            crate::render::default(),
+        )
+        .ok();
+        Delogger::flush();
+        LOGS.lock().unwrap().clear();
+        serial
+    }
+
+    #[test]
+    fn test_report() {
+        let _serial = setup();
+        log::info!("lead-up");
+        super::report(&"panicked at src/main.rs:1:1:\nboom");
+        assert_eq!(
+            *LOGS.lock().unwrap(),
+            "panicked at src/main.rs:1:1:\nboom\nlead-up\n"
+        );
+    }
+
+    #[test]
+    fn test_handle() {
+        let _serial = setup();
+        log::info!("lead-up");
+        super::handle(&format_args!("panicked at {}", "src/main.rs:1:1"));
+        assert_eq!(
+            *LOGS.lock().unwrap(),
+            "panicked at src/main.rs:1:1\nlead-up\n"
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let _serial = setup();
+        PANIC_IN_FLUSHER.store(true, Ordering::SeqCst);
+        assert!(std::panic::catch_unwind(|| super::report(&"first")).is_err());
+        super::report(&"second");
+        assert_eq!(*LOGS.lock().unwrap(), "second\n");
+    }
+
+    type Hook = dyn Fn(&std::panic::PanicHookInfo) + Sync + Send;
+
+    /// Restores the panic hook of the test harness when dropped.
+    struct Restore(Option<std::sync::Arc<Hook>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            // `set_hook` panics while panicking
+            if let (Some(hook), false) = (self.0.take(), std::thread::panicking()) {
+                std::panic::set_hook(std::boxed::Box::new(move |info| hook(info)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hook() {
+        let _serial = setup();
+        let harness: std::sync::Arc<Hook> = std::panic::take_hook().into();
+        let restore = Restore(Some(harness.clone()));
+        static PREVIOUS: AtomicBool = AtomicBool::new(false);
+        let test = std::thread::current().id();
+        std::panic::set_hook(std::boxed::Box::new(move |info| {
+            // panics of tests running in parallel are reported as usual
+            if std::thread::current().id() == test {
+                PREVIOUS.store(true, Ordering::SeqCst)
+            } else {
+                harness(info)
+            }
+        }));
+        super::install_hook();
+
+        log::info!("lead-up");
+        assert!(std::panic::catch_unwind(|| panic!("boom")).is_err());
+        log::info!("recovered");
+        assert!(std::panic::catch_unwind(|| panic!("again")).is_err());
+        // before asserting, so failures are reported as usual
+        drop(restore);
+
+        assert!(PREVIOUS.load(Ordering::SeqCst));
+        let logs = LOGS.lock().unwrap();
+        let logs: std::vec::Vec<&str> = logs.lines().collect();
+        assert_eq!(logs.len(), 6);
+        assert!(logs[0].starts_with("panicked at src/panic.rs:"));
+        assert_eq!(logs[1..3], ["boom", "lead-up"]);
+        assert!(logs[3].starts_with("panicked at src/panic.rs:"));
+        assert_eq!(logs[4..], ["again", "recovered"]);
+    }
+}
@@ -2,6 +2,11 @@
 
 use core::{cmp, fmt};
 
+pub mod cbor;
+pub mod chain;
+pub use cbor::CborRenderer;
+pub use chain::{Chain, Stage};
+
 /// For some reason, there seems to be no existing method to easily render
 /// fmt::Arguments in a pre-allocated byte array.
 ///
@@ -14,28 +19,169 @@ pub fn render_arguments<'a>(buf: &'a mut [u8], args: fmt::Arguments) -> &'a [u8]
     &buf[..used]
 }
 
+/// Render `args` like `render_arguments`, laying out its lines.
+///
+/// ```
+/// use delog::render::{render_arguments_with_layout, Continuation, Layout};
+///
+/// let mut buf = [0u8; 64];
+/// let layout = Layout::new().continuation(Continuation::Prefix("  ")).wrap(4);
+/// assert_eq!(
+///     render_arguments_with_layout(&mut buf, format_args!("abcdef\ngh"), &layout),
+///     b"abcd\n  ef\n  gh\n",
+/// );
+/// ```
+pub fn render_arguments_with_layout<'a>(
+    buf: &'a mut [u8],
+    args: fmt::Arguments,
+    layout: &Layout,
+) -> &'a [u8] {
+    render_with_layout(buf, format_args!(""), args, layout)
+}
+
+/// Render `header` followed by `message`, laying out the lines of the message.
+///
+/// With the default `Layout`, this is the same as rendering both with `render_arguments`.
+/// Otherwise, a trailing newline of the message is dropped, and continuation lines that do
+/// not fit in `buf` are left out entirely.
+///
+/// ```
+/// use delog::render::{render_with_layout, Continuation, Layout};
+///
+/// let mut buf = [0u8; 64];
+/// let layout = Layout::new().continuation(Continuation::RepeatHeader).wrap(12);
+/// assert_eq!(
+///     render_with_layout(&mut buf, format_args!("INFO: "), format_args!("a\nbcdefghijk"), &layout),
+///     b"INFO: a\nINFO: bcdefg\nINFO: hijk\n",
+/// );
+/// ```
+pub fn render_with_layout<'a>(
+    buf: &'a mut [u8],
+    header: fmt::Arguments,
+    message: fmt::Arguments,
+    layout: &Layout,
+) -> &'a [u8] {
+    let mut writer = WriteTo::with_layout(buf, *layout);
+    core::fmt::write(&mut writer, header)
+        .and_then(|()| {
+            writer.mark_header();
+            core::fmt::write(&mut writer, message)
+        })
+        .ok();
+    writer.endl();
+    let used = writer.used;
+    &buf[..used]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// What to write at the start of continuation lines of multi-line messages.
+pub enum Continuation {
+    /// Nothing, lines start at the very left.
+    #[default]
+    Plain,
+    /// The given string, typically some indentation.
+    Prefix(&'static str),
+    /// The header of the record (e.g., level and target), as rendered on the first line.
+    RepeatHeader,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How lines of messages are laid out, for use with `render_with_layout` and the renderers.
+///
+/// The default is to write messages as they are.
+pub struct Layout {
+    continuation: Continuation,
+    width: Option<usize>,
+    truncate: bool,
+    sanitize: bool,
+}
+
+impl Layout {
+    /// Layout that writes messages as they are.
+    pub const fn new() -> Self {
+        Self {
+            continuation: Continuation::Plain,
+            width: None,
+            truncate: false,
+            sanitize: false,
+        }
+    }
+
+    /// Set what to write at the start of continuation lines.
+    pub const fn continuation(mut self, continuation: Continuation) -> Self {
+        self.continuation = continuation;
+        self
+    }
+
+    /// Break lines longer than `width` characters into continuation lines.
+    pub const fn wrap(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self.truncate = false;
+        self
+    }
+
+    /// Cut off lines longer than `width` characters.
+    pub const fn truncate(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self.truncate = true;
+        self
+    }
+
+    /// Escape control characters in messages, including embedded newlines.
+    ///
+    /// This prevents messages from injecting terminal escape sequences, or faking
+    /// further records. Bidirectional text overrides are escaped as well. When wrapping or
+    /// truncating, escapes are kept whole. For plain messages, use it with `LayoutRenderer`
+    /// or `render_arguments_with_layout`.
+    ///
+    /// ```
+    /// use delog::render::{render_with_layout, Layout};
+    ///
+    /// let mut buf = [0u8; 64];
+    /// let layout = Layout::new().sanitize();
+    /// assert_eq!(
+    ///     render_with_layout(&mut buf, format_args!("INFO: "), format_args!("a\nERROR: \x1b[2J"), &layout),
+    ///     b"INFO: a\\nERROR: \\u{1b}[2J\n",
+    /// );
+    /// ```
+    pub const fn sanitize(mut self) -> Self {
+        self.sanitize = true;
+        self
+    }
+
+    fn is_plain(&self) -> bool {
+        self.continuation == Continuation::Plain && self.width.is_none() && !self.sanitize
+    }
+}
+
 /// Render record, based on feature flags.
 pub fn render_record<'a>(buf: &'a mut [u8], record: &log::Record) -> &'a [u8] {
+    render_record_with_layout(buf, record, &Layout::new())
+}
+
+/// Render record like `render_record`, laying out the lines of its message.
+pub fn render_record_with_layout<'a>(
+    buf: &'a mut [u8],
+    record: &log::Record,
+    layout: &Layout,
+) -> &'a [u8] {
     if cfg!(feature = "prefix-level") {
         match (record.file(), record.line()) {
-            (Some(file), Some(line)) => render_arguments(
+            (Some(file), Some(line)) => render_with_layout(
                 buf,
-                format_args!(
-                    "{}|{}|{}:{}: {}",
-                    record.level(),
-                    record.target(),
-                    file,
-                    line,
-                    record.args()
-                ),
+                format_args!("{}|{}|{}:{}: ", record.level(), record.target(), file, line,),
+                *record.args(),
+                layout,
             ),
-            _ => render_arguments(
+            _ => render_with_layout(
                 buf,
-                format_args!("{}|{}: {}", record.level(), record.target(), record.args()),
+                format_args!("{}|{}: ", record.level(), record.target()),
+                *record.args(),
+                layout,
             ),
         }
     } else {
-        render_arguments(buf, *record.args())
+        render_arguments_with_layout(buf, *record.args(), layout)
     }
 }
 
@@ -45,11 +191,81 @@ struct WriteTo<'a> {
     // on write error (i.e. not enough space in buffer) this grows beyond
     // `buffer.len()`.
     used: usize,
+    layout: Layout,
+    // end of the header, as repeated by `Continuation::RepeatHeader`
+    header: usize,
+    // layout applies to the message only, not the header
+    message: bool,
+    // characters in the current line
+    column: usize,
+    // characters of header or continuation, wrapping before these would not make progress
+    indent: usize,
+    // cut off rest of the current line
+    skipping: bool,
+    // a newline was written, whose continuation is deferred until the next character
+    newline_pending: bool,
+    // escape sequences (e.g. hyperlinks) take no columns
+    escape: Escape,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Where in a terminal escape sequence the text is.
+enum Escape {
+    #[default]
+    None,
+    Start,
+    Csi,
+    Osc,
+    OscEnd,
+}
+
+impl Escape {
+    /// The state after `c`, and whether `c` is visible.
+    fn next(self, c: char) -> (Self, bool) {
+        match (self, c) {
+            (Escape::None, '\x1b') => (Escape::Start, false),
+            (Escape::None, _) => (Escape::None, true),
+            (Escape::Start, '[') => (Escape::Csi, false),
+            (Escape::Start, ']') => (Escape::Osc, false),
+            (Escape::Start, _) => (Escape::None, false),
+            (Escape::Csi, '\x40'..='\x7e') => (Escape::None, false),
+            (Escape::Csi, _) => (Escape::Csi, false),
+            (Escape::Osc, '\x07') => (Escape::None, false),
+            (Escape::Osc, '\x1b') => (Escape::OscEnd, false),
+            (Escape::Osc, _) => (Escape::Osc, false),
+            (Escape::OscEnd, _) => (Escape::None, false),
+        }
+    }
+}
+
+/// Number of visible characters in `s`, skipping escape sequences.
+fn columns(s: &str) -> usize {
+    s.chars()
+        .fold((Escape::None, 0), |(escape, columns), c| {
+            let (escape, visible) = escape.next(c);
+            (escape, columns + visible as usize)
+        })
+        .1
 }
 
 impl<'a> WriteTo<'a> {
     pub fn new(buffer: &'a mut [u8]) -> Self {
-        WriteTo { buffer, used: 0 }
+        Self::with_layout(buffer, Layout::new())
+    }
+
+    pub fn with_layout(buffer: &'a mut [u8], layout: Layout) -> Self {
+        WriteTo {
+            buffer,
+            used: 0,
+            layout,
+            header: 0,
+            message: false,
+            column: 0,
+            indent: 0,
+            skipping: false,
+            newline_pending: false,
+            escape: Escape::None,
+        }
     }
 
     pub fn endl(&mut self) {
@@ -58,10 +274,127 @@ impl<'a> WriteTo<'a> {
             self.used += 1;
         }
     }
+
+    pub fn mark_header(&mut self) {
+        self.header = self.used;
+        self.message = true;
+        self.indent = self.column;
+    }
+
+    // all or nothing, so we never split characters
+    fn push(&mut self, bytes: &[u8]) -> fmt::Result {
+        let end = self.used + bytes.len();
+        if end > self.buffer.len() {
+            return Err(fmt::Error);
+        }
+        self.buffer[self.used..end].copy_from_slice(bytes);
+        self.used = end;
+        Ok(())
+    }
+
+    // the line break and its continuation, or nothing if they do not both fit
+    fn newline(&mut self) -> fmt::Result {
+        let continuation = match self.layout.continuation {
+            Continuation::Plain => 0,
+            Continuation::Prefix(prefix) => prefix.len(),
+            Continuation::RepeatHeader => self.header,
+        };
+        if self.used + 1 + continuation > self.buffer.len() {
+            return Err(fmt::Error);
+        }
+        self.push(b"\n")?;
+        self.skipping = false;
+        self.indent = match self.layout.continuation {
+            Continuation::Plain => 0,
+            Continuation::Prefix(prefix) => {
+                self.push(prefix.as_bytes())?;
+                columns(prefix)
+            }
+            Continuation::RepeatHeader => {
+                let header = self.header;
+                self.buffer.copy_within(..header, self.used);
+                self.used += header;
+                // written as `str`, and cut off on character boundaries only
+                columns(core::str::from_utf8(&self.buffer[..header]).unwrap_or(""))
+            }
+        };
+        self.column = self.indent;
+        Ok(())
+    }
+
+    fn put(&mut self, c: char) -> fmt::Result {
+        if self.newline_pending {
+            self.newline_pending = false;
+            self.newline()?;
+        }
+        if self.skipping {
+            return Ok(());
+        }
+        let (escape, visible) = self.escape.next(c);
+        if let (true, true, Some(width)) = (visible, self.message, self.layout.width) {
+            if self.column >= width && self.column > self.indent {
+                if self.layout.truncate {
+                    self.skipping = true;
+                    return Ok(());
+                }
+                self.newline()?;
+            }
+        }
+        self.push(c.encode_utf8(&mut [0u8; 4]).as_bytes())?;
+        self.escape = escape;
+        self.column += visible as usize;
+        Ok(())
+    }
+
+    // keeps the escape on one line, if it fits at all
+    fn put_escaped(&mut self, c: char) -> fmt::Result {
+        if self.newline_pending {
+            self.newline_pending = false;
+            self.newline()?;
+        }
+        if let (false, Some(width)) = (self.skipping, self.layout.width) {
+            let len = c.escape_default().count();
+            if self.column + len > width && self.column > self.indent {
+                if self.layout.truncate {
+                    self.skipping = true;
+                    return Ok(());
+                }
+                self.newline()?;
+            }
+        }
+        for escaped in c.escape_default() {
+            self.put(escaped)?;
+        }
+        Ok(())
+    }
+
+    fn write_laid_out(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.message && self.layout.sanitize && is_unsafe(c) {
+                self.put_escaped(c)?;
+            } else if c == '\n' {
+                if self.newline_pending {
+                    self.newline()?;
+                }
+                self.newline_pending = true;
+            } else {
+                self.put(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Control characters, and the bidirectional formatting characters that can reorder text.
+fn is_unsafe(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
 }
 
 impl<'a> core::fmt::Write for WriteTo<'a> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        if !self.layout.is_plain() {
+            return self.write_laid_out(s);
+        }
         if self.used > self.buffer.len() {
             return Err(fmt::Error);
         }
@@ -99,38 +432,435 @@ impl Renderer for DefaultRenderer {
 unsafe impl Send for DefaultRenderer {}
 unsafe impl Sync for DefaultRenderer {}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
+/// Renders just the `record.args()`, like `DefaultRenderer`, laying out their lines.
+///
+/// ```
+/// use delog::render::{Continuation, Layout, LayoutRenderer};
+/// use delog::Renderer;
+///
+/// static RENDERER: LayoutRenderer =
+///     LayoutRenderer::new(Layout::new().continuation(Continuation::Prefix("> ")));
+///
+/// let mut buf = [0u8; 64];
+/// let record = log::Record::builder()
+///     .args(format_args!("a\nb\n"))
+///     .build();
+/// assert_eq!(RENDERER.render(&mut buf, &record), b"a\n> b\n");
+/// ```
+pub struct LayoutRenderer {
+    layout: Layout,
+}
+
+impl LayoutRenderer {
+    /// Renderer laying out messages with `layout`.
+    pub const fn new(layout: Layout) -> Self {
+        Self { layout }
+    }
+}
+
+impl Renderer for LayoutRenderer {
+    fn render<'a>(&self, buf: &'a mut [u8], record: &log::Record) -> &'a [u8] {
+        render_arguments_with_layout(buf, *record.args(), &self.layout)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How `RipgrepRenderer` renders the location of a record.
+pub enum PathStyle {
+    /// The file as recorded by `file!()`.
+    #[default]
+    Full,
+    /// The file, with the given prefix removed if present.
+    StripPrefix(&'static str),
+    /// The file, relative to the directory containing its crate,
+    /// e.g. `log-0.4.20/src/lib.rs` instead of `~/.cargo/registry/src/<index>/log-0.4.20/src/lib.rs`.
+    CrateRelative,
+    /// The module path instead of file and line.
+    ModulePath,
+}
+
+impl PathStyle {
+    fn shorten<'a>(&self, file: &'a str) -> &'a str {
+        match *self {
+            PathStyle::StripPrefix(prefix) => file.strip_prefix(prefix).unwrap_or(file),
+            PathStyle::CrateRelative => {
+                let src = file.rfind("/src/").or_else(|| file.rfind("\\src\\"));
+                match src.and_then(|src| file[..src].rfind(['/', '\\'])) {
+                    Some(separator) => &file[separator + 1..],
+                    None => file,
+                }
+            }
+            _ => file,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
 /// Renders the `record.args()`, prefixed by level, target, and file, line if they are some.
-pub struct RipgrepRenderer {}
+///
+/// ```
+/// use delog::render::{PathStyle, RipgrepRenderer};
+/// use delog::Renderer;
+///
+/// let mut buf = [0u8; 64];
+/// let record = log::Record::builder()
+///     .args(format_args!("hello"))
+///     .target("log")
+///     .module_path(Some("log::kv"))
+///     .file(Some("/home/me/.cargo/registry/src/index/log-0.4.20/src/kv.rs"))
+///     .line(Some(7))
+///     .build();
+///
+/// static SHORT: RipgrepRenderer = RipgrepRenderer::new().path_style(PathStyle::CrateRelative);
+/// assert_eq!(SHORT.render(&mut buf, &record), b"INFO|log|log-0.4.20/src/kv.rs:7: hello\n");
+///
+/// static MODULE: RipgrepRenderer = RipgrepRenderer::new().path_style(PathStyle::ModulePath);
+/// assert_eq!(MODULE.render(&mut buf, &record), b"INFO|log|log::kv: hello\n");
+/// ```
+pub struct RipgrepRenderer {
+    path_style: PathStyle,
+    layout: Layout,
+    // base directory of relative files, if hyperlinks are on
+    hyperlinks: Option<&'static str>,
+}
+
+impl RipgrepRenderer {
+    /// Renderer with full paths (and without hyperlinks).
+    pub const fn new() -> Self {
+        Self {
+            path_style: PathStyle::Full,
+            layout: Layout::new(),
+            hyperlinks: None,
+        }
+    }
+
+    /// Set how the location of records is rendered.
+    pub const fn path_style(mut self, path_style: PathStyle) -> Self {
+        self.path_style = path_style;
+        self
+    }
+
+    /// Set how the lines of messages are laid out, e.g. to repeat the
+    /// level, target and location on continuation lines.
+    pub const fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Wrap the location of records in OSC 8 terminal hyperlinks to their file and line,
+    /// or not if `None`.
+    ///
+    /// Relative files, as recorded by `file!()` for the crates of a workspace, are resolved
+    /// against the given base directory, i.e. the workspace root. For a single package, that
+    /// is `env!("CARGO_MANIFEST_DIR")`.
+    ///
+    /// ```
+    /// use delog::render::RipgrepRenderer;
+    /// use delog::Renderer;
+    ///
+    /// static RENDERER: RipgrepRenderer = RipgrepRenderer::new().hyperlinks(Some("/home/me/app"));
+    ///
+    /// let mut buf = [0u8; 128];
+    /// let record = log::Record::builder()
+    ///     .args(format_args!("hello"))
+    ///     .target("app")
+    ///     .file(Some("src/my main.rs"))
+    ///     .line(Some(7))
+    ///     .build();
+    /// assert_eq!(
+    ///     RENDERER.render(&mut buf, &record),
+    ///     b"INFO|app|\x1b]8;;file:///home/me/app/src/my%20main.rs#7\x1b\\src/my main.rs:7\x1b]8;;\x1b\\: hello\n",
+    /// );
+    /// ```
+    pub const fn hyperlinks(mut self, base: Option<&'static str>) -> Self {
+        self.hyperlinks = base;
+        self
+    }
+}
+
+struct Location<'a> {
+    renderer: &'a RipgrepRenderer,
+    record: &'a log::Record<'a>,
+}
+
+impl Location<'_> {
+    fn is_some(&self) -> bool {
+        match self.renderer.path_style {
+            PathStyle::ModulePath => self.record.module_path().is_some(),
+            _ => self.record.file().is_some(),
+        }
+    }
+
+    fn fmt_text(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        let style = &self.renderer.path_style;
+        match (
+            style,
+            self.record.module_path(),
+            self.record.file(),
+            self.record.line(),
+        ) {
+            (PathStyle::ModulePath, Some(module_path), _, _) => f.write_str(module_path),
+            (_, _, Some(file), Some(line)) => write!(f, "{}:{}", style.shorten(file), line),
+            (_, _, Some(file), None) => f.write_str(style.shorten(file)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(base), Some(file)) = (self.renderer.hyperlinks, self.record.file()) {
+            f.write_str("\x1b]8;;file://")?;
+            if !is_absolute(file) {
+                write_url_path(f, base)?;
+                if !base.ends_with(['/', '\\']) {
+                    f.write_str("/")?;
+                }
+            }
+            write_url_path(f, file)?;
+            if let Some(line) = self.record.line() {
+                write!(f, "#{}", line)?;
+            }
+            f.write_str("\x1b\\")?;
+            // the text must not end the hyperlink early
+            self.fmt_text(&mut Sanitized(f))?;
+            return f.write_str("\x1b]8;;\x1b\\");
+        }
+        self.fmt_text(f)
+    }
+}
+
+/// Escapes control characters written to the inner formatter, see `Layout::sanitize`.
+struct Sanitized<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for Sanitized<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if is_unsafe(c) {
+                write!(self.0, "{}", c.escape_default())?;
+            } else {
+                fmt::Write::write_char(self.0, c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_drive(path: &str) -> bool {
+    let path = path.as_bytes();
+    path.len() >= 2 && path[0].is_ascii_alphabetic() && path[1] == b':'
+}
+
+fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || has_drive(path)
+}
+
+/// Writes `path` as path of a URL, percent-encoding all but unreserved characters and `/`.
+fn write_url_path(f: &mut fmt::Formatter, path: &str) -> fmt::Result {
+    if has_drive(path) {
+        f.write_str("/")?;
+    }
+    for &byte in path.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                fmt::Write::write_char(f, byte as char)?
+            }
+            _ => write!(f, "%{:02X}", byte)?,
+        }
+    }
+    Ok(())
+}
 
 impl Renderer for RipgrepRenderer {
     fn render<'a>(&self, buf: &'a mut [u8], record: &log::Record) -> &'a [u8] {
-        match (record.file(), record.line()) {
-            (Some(file), Some(line)) => render_arguments(
+        let location = Location {
+            renderer: self,
+            record,
+        };
+        if location.is_some() {
+            render_with_layout(
                 buf,
-                format_args!(
-                    "{}|{}|{}:{}: {}",
-                    record.level(),
-                    record.target(),
-                    file,
-                    line,
-                    record.args()
-                ),
-            ),
-            (Some(file), None) => render_arguments(
-                buf,
-                format_args!(
-                    "{}|{}|{}: {}",
-                    record.level(),
-                    record.target(),
-                    file,
-                    record.args()
-                ),
-            ),
-            _ => render_arguments(
+                format_args!("{}|{}|{}: ", record.level(), record.target(), location),
+                *record.args(),
+                &self.layout,
+            )
+        } else {
+            render_with_layout(
                 buf,
-                format_args!("{}|{}: {}", record.level(), record.target(), record.args()),
-            ),
+                format_args!("{}|{}: ", record.level(), record.target()),
+                *record.args(),
+                &self.layout,
+            )
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{render_with_layout, Continuation, Layout, PathStyle, RipgrepRenderer};
+    use crate::Renderer;
+
+    fn render(buf: &mut [u8], message: &str, layout: Layout) -> std::string::String {
+        let rendered = render_with_layout(
+            buf,
+            format_args!("INFO: "),
+            format_args!("{}", message),
+            &layout,
+        );
+        core::str::from_utf8(rendered).unwrap().into()
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let mut buf = [0u8; 64];
+        let layout = Layout::new().continuation(Continuation::RepeatHeader);
+        assert_eq!(render(&mut buf, "a\n", layout), "INFO: a\n");
+        assert_eq!(render(&mut buf, "a\n\n", layout), "INFO: a\nINFO: \n");
+        assert_eq!(
+            render(&mut buf, "a\n\nb", layout),
+            "INFO: a\nINFO: \nINFO: b\n"
+        );
+        let layout = Layout::new().wrap(10);
+        assert_eq!(render(&mut buf, "abcd\n", layout), "INFO: abcd\n");
+    }
+
+    #[test]
+    fn test_repeat_header_overflow() {
+        let layout = Layout::new().continuation(Continuation::RepeatHeader);
+        // no room for the repeated header, so the continuation line is left out
+        let mut buf = [0u8; 12];
+        assert_eq!(render(&mut buf, "a\nb", layout), "INFO: a\n");
+        // exactly enough room
+        let mut buf = [0u8; 16];
+        assert_eq!(render(&mut buf, "a\nb", layout), "INFO: a\nINFO: b\n");
+    }
+
+    #[test]
+    fn test_truncate_prefix() {
+        let mut buf = [0u8; 64];
+        let layout = Layout::new()
+            .continuation(Continuation::Prefix("  "))
+            .truncate(8);
+        assert_eq!(
+            render(&mut buf, "abcdefgh\nijklmnopq\nr", layout),
+            "INFO: ab\n  ijklmn\n  r\n"
+        );
+        // a prefix as long as the width still lets each line make progress
+        let layout = Layout::new()
+            .continuation(Continuation::Prefix("........"))
+            .truncate(8);
+        assert_eq!(render(&mut buf, "a\nbc", layout), "INFO: a\n........b\n");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let mut buf = [0u8; 64];
+        let layout = Layout::new().sanitize();
+        assert_eq!(render(&mut buf, "a\rb", layout), "INFO: a\\rb\n");
+        assert_eq!(
+            render(&mut buf, "\x1b[31mred\x1b[0m", layout),
+            "INFO: \\u{1b}[31mred\\u{1b}[0m\n"
+        );
+        assert_eq!(
+            render(&mut buf, "a\u{202e}b\u{2066}c\u{2069}", layout),
+            "INFO: a\\u{202e}b\\u{2066}c\\u{2069}\n"
+        );
+        // other non-ASCII text is left alone
+        assert_eq!(render(&mut buf, "größe", layout), "INFO: größe\n");
+    }
+
+    #[test]
+    fn test_sanitize_wrap() {
+        let mut buf = [0u8; 64];
+        let layout = Layout::new().sanitize().wrap(10);
+        assert_eq!(
+            render(&mut buf, "ab\x1bcd", layout),
+            "INFO: ab\n\\u{1b}cd\n"
+        );
+        assert_eq!(render(&mut buf, "abc\nd", layout), "INFO: abc\n\\nd\n");
+        let layout = Layout::new()
+            .sanitize()
+            .continuation(Continuation::Prefix("  "))
+            .truncate(10);
+        assert_eq!(render(&mut buf, "ab\x1bcd\ne", layout), "INFO: ab\n");
+    }
+
+    #[test]
+    fn test_path_style() {
+        let style = PathStyle::StripPrefix("/home/me/app/");
+        assert_eq!(style.shorten("/home/me/app/src/main.rs"), "src/main.rs");
+        assert_eq!(
+            style.shorten("/home/you/src/main.rs"),
+            "/home/you/src/main.rs"
+        );
+
+        let style = PathStyle::CrateRelative;
+        assert_eq!(
+            style.shorten("C:\\Users\\me\\.cargo\\registry\\src\\index\\log-0.4.20\\src\\kv.rs"),
+            "log-0.4.20\\src\\kv.rs"
+        );
+        assert_eq!(style.shorten("src\\kv.rs"), "src\\kv.rs");
+    }
+
+    fn link(renderer: RipgrepRenderer, file: &str) -> std::string::String {
+        let mut buf = [0u8; 256];
+        let record = log::Record::builder()
+            .args(format_args!("hi"))
+            .target("app")
+            .file(Some(file))
+            .line(Some(3))
+            .build();
+        core::str::from_utf8(renderer.render(&mut buf, &record))
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let renderer = RipgrepRenderer::new().hyperlinks(Some("/ws/"));
+        assert_eq!(
+            link(renderer, "src/a+b.rs"),
+            "INFO|app|\x1b]8;;file:///ws/src/a%2Bb.rs#3\x1b\\src/a+b.rs:3\x1b]8;;\x1b\\: hi\n"
+        );
+        // absolute files ignore the base, and the path style only applies to the text
+        let renderer = renderer.path_style(PathStyle::CrateRelative);
+        assert_eq!(
+            link(renderer, "C:\\reg\\log-0.4.20\\src\\kv.rs"),
+            "INFO|app|\x1b]8;;file:///C:%5Creg%5Clog-0.4.20%5Csrc%5Ckv.rs#3\x1b\\log-0.4.20\\src\\kv.rs:3\x1b]8;;\x1b\\: hi\n"
+        );
+        // nothing of hostile paths passes through unencoded
+        assert_eq!(
+            link(RipgrepRenderer::new().hyperlinks(Some("/ws")), "src\\%1b\x1b]8;;x\\.rs"),
+            "INFO|app|\x1b]8;;file:///ws/src%5C%251b%1B%5D8%3B%3Bx%5C.rs#3\x1b\\src\\%1b\\u{1b}]8;;x\\.rs:3\x1b]8;;\x1b\\: hi\n"
+        );
+        assert_eq!(
+            link(RipgrepRenderer::new(), "src/a.rs"),
+            "INFO|app|src/a.rs:3: hi\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_hyperlinks() {
+        let mut buf = [0u8; 256];
+        let renderer = RipgrepRenderer::new().hyperlinks(Some("/ws")).layout(
+            Layout::new()
+                .continuation(Continuation::RepeatHeader)
+                .wrap(25),
+        );
+        let record = log::Record::builder()
+            .args(format_args!("abcdefgh"))
+            .target("app")
+            .file(Some("src/a.rs"))
+            .line(Some(3))
+            .build();
+        // the visible header `INFO|app|src/a.rs:3: ` leaves four columns
+        let header = "INFO|app|\x1b]8;;file:///ws/src/a.rs#3\x1b\\src/a.rs:3\x1b]8;;\x1b\\: ";
+        assert_eq!(
+            core::str::from_utf8(renderer.render(&mut buf, &record)).unwrap(),
+            format!("{0}abcd\n{0}efgh\n", header)
+        );
+        assert_eq!(super::columns(header), 21);
+        assert_eq!(super::columns("\x1b[1mbold\x1b[0m\x1bM"), 4);
+    }
+}
@@ -0,0 +1,362 @@
+//! A renderer that encodes records as compact CBOR maps.
+//!
+//! Each record is encoded as one CBOR map with small integer keys, the flushed stream
+//! hence is a CBOR sequence (RFC 8742) that host tools can decode item by item.
+//!
+//! | key | value |
+//! |-----|-------|
+//! | [`LEVEL`] | level as unsigned integer, `1` (error) to `5` (trace) |
+//! | [`TIMESTAMP`] | unsigned integer from the renderer's clock, if configured |
+//! | [`TARGET`] | text |
+//! | [`FILE`] | text, if present |
+//! | [`LINE`] | unsigned integer, if present |
+//! | [`KEY_VALUES`] | map of text keys to integer, boolean or text values, if any (requires the `kv` feature) |
+//! | [`MESSAGE`] | text |
+//!
+//! The message comes last, so that it is the only entry that gets truncated (on a UTF-8
+//! character boundary) if the render buffer is too small. Records whose other entries do
+//! not fit, including the text of target, file and key-values, are dropped entirely.
+//!
+//! As the output is not UTF-8, the flusher should override [`Flusher::flush_bytes`][crate::Flusher::flush_bytes]:
+//!
+//! ```
+//! #[derive(Debug)]
+//! struct RawFlusher {}
+//!
+//! impl delog::Flusher for RawFlusher {
+//!     fn flush(&self, logs: &str) {
+//!         self.flush_bytes(logs.as_bytes())
+//!     }
+//!
+//!     fn flush_bytes(&self, logs: &[u8]) {
+//!         // e.g. send over serial, as is
+//!         # let _ = logs;
+//!     }
+//! }
+//!
+//! delog::delog!(Delogger, 256, RawFlusher, renderer: delog::render::CborRenderer);
+//! ```
+
+use core::fmt;
+
+use super::WriteTo;
+use crate::Renderer;
+
+/// Map key of the level.
+pub const LEVEL: u8 = 0;
+/// Map key of the timestamp.
+pub const TIMESTAMP: u8 = 1;
+/// Map key of the target.
+pub const TARGET: u8 = 2;
+/// Map key of the file.
+pub const FILE: u8 = 3;
+/// Map key of the line.
+pub const LINE: u8 = 4;
+/// Map key of the structured key-values.
+pub const KEY_VALUES: u8 = 5;
+/// Map key of the message.
+pub const MESSAGE: u8 = 6;
+
+#[derive(Clone, Copy, Default)]
+/// Renders records as CBOR maps, optionally timestamped.
+///
+/// ```
+/// use delog::{render::CborRenderer, Renderer};
+///
+/// let mut buf = [0u8; 32];
+/// let record = log::Record::builder()
+///     .args(format_args!("hi"))
+///     .level(log::Level::Info)
+///     .target("app")
+///     .build();
+///
+/// assert_eq!(
+///     CborRenderer::new().render(&mut buf, &record),
+///     &[0xa3, 0x00, 0x03, 0x02, 0x63, b'a', b'p', b'p', 0x06, 0x62, b'h', b'i'],
+/// );
+/// ```
+pub struct CborRenderer {
+    clock: Option<fn() -> u64>,
+}
+
+impl CborRenderer {
+    /// Renderer without timestamps.
+    pub const fn new() -> Self {
+        Self { clock: None }
+    }
+
+    /// Renderer that timestamps each record with the value returned by `clock`.
+    pub const fn with_clock(clock: fn() -> u64) -> Self {
+        Self { clock: Some(clock) }
+    }
+}
+
+impl Renderer for CborRenderer {
+    fn render<'a>(&self, buf: &'a mut [u8], record: &log::Record) -> &'a [u8] {
+        let mut encoder = Encoder {
+            buffer: &mut *buf,
+            used: 0,
+        };
+        let used = match encoder.record(record, self.clock) {
+            Ok(()) => encoder.used,
+            Err(_) => 0,
+        };
+        &buf[..used]
+    }
+}
+
+const MAJOR_UNSIGNED: u8 = 0;
+#[cfg(feature = "kv")]
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_MAP: u8 = 5;
+#[cfg(feature = "kv")]
+const MAJOR_SIMPLE: u8 = 7;
+
+/// Length of the head encoding `argument`.
+fn head_len(argument: u64) -> usize {
+    match argument {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+struct Encoder<'a> {
+    buffer: &'a mut [u8],
+    used: usize,
+}
+
+impl Encoder<'_> {
+    fn bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        let end = self.used + bytes.len();
+        if end > self.buffer.len() {
+            return Err(fmt::Error);
+        }
+        self.buffer[self.used..end].copy_from_slice(bytes);
+        self.used = end;
+        Ok(())
+    }
+
+    fn head(&mut self, major: u8, argument: u64) -> fmt::Result {
+        let major = major << 5;
+        match argument {
+            0..=23 => self.bytes(&[major | argument as u8]),
+            24..=0xff => self.bytes(&[major | 24, argument as u8]),
+            0x100..=0xffff => {
+                self.bytes(&[major | 25])?;
+                self.bytes(&(argument as u16).to_be_bytes())
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.bytes(&[major | 26])?;
+                self.bytes(&(argument as u32).to_be_bytes())
+            }
+            _ => {
+                self.bytes(&[major | 27])?;
+                self.bytes(&argument.to_be_bytes())
+            }
+        }
+    }
+
+    fn unsigned(&mut self, value: u64) -> fmt::Result {
+        self.head(MAJOR_UNSIGNED, value)
+    }
+
+    /// Encodes the formatted arguments as text string, truncated to the available space
+    /// if `truncate` is set, failing otherwise.
+    fn text(&mut self, args: fmt::Arguments, truncate: bool) -> fmt::Result {
+        let available = self.buffer.len() - self.used;
+        if available == 0 {
+            return Err(fmt::Error);
+        }
+        // room for the longest text that fits, with the head this needs at most
+        let content = self.used + head_len(available as u64 - 1);
+
+        let mut writer = WriteTo::new(&mut self.buffer[content..]);
+        let result = fmt::write(&mut writer, args);
+        if result.is_err() && !truncate {
+            return result;
+        }
+        let used = writer.used;
+        let written = &self.buffer[content..][..used];
+        // truncation may have split a character
+        let len = match core::str::from_utf8(written) {
+            Ok(_) => written.len(),
+            Err(error) => error.valid_up_to(),
+        };
+
+        self.head(MAJOR_TEXT, len as u64)?;
+        self.buffer.copy_within(content..content + len, self.used);
+        self.used += len;
+        Ok(())
+    }
+
+    fn record(&mut self, record: &log::Record, clock: Option<fn() -> u64>) -> fmt::Result {
+        #[cfg(feature = "kv")]
+        let key_values = log::kv::Source::count(record.key_values());
+        #[cfg(not(feature = "kv"))]
+        let key_values = 0;
+
+        let entries = 3
+            + clock.is_some() as u64
+            + record.file().is_some() as u64
+            + record.line().is_some() as u64
+            + (key_values > 0) as u64;
+        self.head(MAJOR_MAP, entries)?;
+
+        self.unsigned(LEVEL as u64)?;
+        self.unsigned(record.level() as u64)?;
+
+        if let Some(clock) = clock {
+            self.unsigned(TIMESTAMP as u64)?;
+            self.unsigned(clock())?;
+        }
+
+        self.unsigned(TARGET as u64)?;
+        self.text(format_args!("{}", record.target()), false)?;
+
+        if let Some(file) = record.file() {
+            self.unsigned(FILE as u64)?;
+            self.text(format_args!("{}", file), false)?;
+        }
+
+        if let Some(line) = record.line() {
+            self.unsigned(LINE as u64)?;
+            self.unsigned(line as u64)?;
+        }
+
+        #[cfg(feature = "kv")]
+        if key_values > 0 {
+            self.unsigned(KEY_VALUES as u64)?;
+            self.head(MAJOR_MAP, key_values as u64)?;
+            log::kv::Source::visit(record.key_values(), &mut KeyValues(self))
+                .map_err(|_| fmt::Error)?;
+        }
+
+        self.unsigned(MESSAGE as u64)?;
+        self.text(*record.args(), true)
+    }
+}
+
+#[cfg(feature = "kv")]
+struct KeyValues<'e, 'a>(&'e mut Encoder<'a>);
+
+#[cfg(feature = "kv")]
+impl KeyValues<'_, '_> {
+    fn value(&mut self, value: &log::kv::Value) -> fmt::Result {
+        if let Some(value) = value.to_u64() {
+            self.0.unsigned(value)
+        } else if let Some(value) = value.to_i64() {
+            // `to_u64` failed, so this is negative
+            self.0.head(MAJOR_NEGATIVE, !(value as u64))
+        } else if let Some(value) = value.to_bool() {
+            self.0
+                .bytes(&[(MAJOR_SIMPLE << 5) | if value { 21 } else { 20 }])
+        } else {
+            self.0.text(format_args!("{}", value), false)
+        }
+    }
+}
+
+#[cfg(feature = "kv")]
+impl<'kvs> log::kv::VisitSource<'kvs> for KeyValues<'_, '_> {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.0
+            .text(format_args!("{}", key), false)
+            .and_then(|()| self.value(&value))
+            .map_err(|_| log::kv::Error::msg("render buffer exhausted"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CborRenderer;
+    use crate::Renderer;
+
+    fn record<'a>(args: core::fmt::Arguments<'a>) -> log::RecordBuilder<'a> {
+        let mut builder = log::Record::builder();
+        builder.args(args).level(log::Level::Warn).target("app");
+        builder
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let mut buf = [0u8; 32];
+        let renderer = CborRenderer::with_clock(|| 1000);
+        assert_eq!(
+            renderer.render(&mut buf, &record(format_args!("")).build()),
+            &[0xa4, 0x00, 0x02, 0x01, 0x19, 0x03, 0xe8, 0x02, 0x63, b'a', b'p', b'p', 0x06, 0x60],
+        );
+    }
+
+    #[test]
+    fn test_location() {
+        let mut buf = [0u8; 32];
+        let record = record(format_args!("hi"))
+            .file(Some("a.rs"))
+            .line(Some(300))
+            .build();
+        assert_eq!(
+            CborRenderer::new().render(&mut buf, &record),
+            &[
+                0xa5, 0x00, 0x02, 0x02, 0x63, b'a', b'p', b'p', 0x03, 0x64, b'a', b'.', b'r', b's',
+                0x04, 0x19, 0x01, 0x2c, 0x06, 0x62, b'h', b'i',
+            ],
+        );
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn test_key_values() {
+        let mut buf = [0u8; 32];
+        let key_values = [
+            ("n", log::kv::Value::from(-2i64)),
+            ("ok", log::kv::Value::from(true)),
+        ];
+        let record = record(format_args!("")).key_values(&key_values).build();
+        assert_eq!(
+            CborRenderer::new().render(&mut buf, &record),
+            &[
+                0xa4, 0x00, 0x02, 0x02, 0x63, b'a', b'p', b'p', 0x05, 0xa2, 0x61, b'n', 0x21, 0x62,
+                b'o', b'k', 0xf5, 0x06, 0x60,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_truncation() {
+        let header = [0xa3, 0x00, 0x02, 0x02, 0x63, b'a', b'p', b'p', 0x06];
+        // short messages fit exactly
+        let mut buf = [0u8; 12];
+        let rendered = CborRenderer::new().render(&mut buf, &record(format_args!("hi")).build());
+        assert_eq!(rendered[..9], header);
+        assert_eq!(rendered[9..], [0x62, b'h', b'i']);
+        // longer ones are cut off on character boundaries
+        let mut buf = [0u8; 14];
+        let rendered = CborRenderer::new().render(&mut buf, &record(format_args!("abcä")).build());
+        assert_eq!(rendered[9..], [0x63, b'a', b'b', b'c']);
+        // ... also if the head of their full length would be longer
+        let mut buf = [0u8; 35];
+        let long = "x".repeat(300);
+        let rendered =
+            CborRenderer::new().render(&mut buf, &record(format_args!("{}", long)).build());
+        assert_eq!(rendered.len(), 35);
+        assert_eq!(rendered[9..11], [0x78, 24]);
+        // without room for the message, the record is dropped
+        let mut buf = [0u8; 9];
+        assert!(CborRenderer::new()
+            .render(&mut buf, &record(format_args!("hi")).build())
+            .is_empty());
+        // as it is if the target does not fit
+        let mut buf = [0u8; 7];
+        assert!(CborRenderer::new()
+            .render(&mut buf, &record(format_args!("")).build())
+            .is_empty());
+    }
+}
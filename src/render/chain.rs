@@ -0,0 +1,186 @@
+//! Composable renderer pipelines.
+//!
+//! A [`Chain`] runs a [`Stage`] in front of another renderer, which may itself be a chain.
+//! Stages decide whether, and in which form, a record is passed on, so filtering and
+//! enrichment can be combined with any of the existing renderers as final formatter.
+//!
+//! Chains are assembled statically, and can be used as `renderer` in the `delog!` macro:
+//!
+//! ```
+//! use delog::render::{chain::{Filter, Prefix}, Chain, RipgrepRenderer};
+//! use delog::Renderer;
+//!
+//! type Pipeline = Chain<Filter, Chain<Prefix, RipgrepRenderer>>;
+//!
+//! static RENDERER: Pipeline = Chain::new(
+//!     Filter(|record| record.target() != "noisy"),
+//!     Chain::new(Prefix(|f, _| f.write_str("[task 1] ")), RipgrepRenderer::new()),
+//! );
+//!
+//! let mut buf = [0u8; 64];
+//! let record = log::Record::builder()
+//!     .args(format_args!("hello"))
+//!     .target("app")
+//!     .build();
+//! assert_eq!(RENDERER.render(&mut buf, &record), b"INFO|app: [task 1] hello\n");
+//!
+//! let record = log::Record::builder()
+//!     .args(format_args!("hello"))
+//!     .target("noisy")
+//!     .build();
+//! assert!(RENDERER.render(&mut buf, &record).is_empty());
+//!
+//! # #[derive(Debug)]
+//! # struct Flusher {}
+//! # impl delog::Flusher for Flusher { fn flush(&self, _: &str) {} }
+//! delog::delog!(Delogger, 256, Flusher, renderer: Pipeline);
+//! ```
+
+use core::fmt;
+
+use crate::Renderer;
+
+/// A step in a renderer pipeline.
+pub trait Stage: Send + Sync {
+    /// Implementor must either pass on the (possibly rewritten) record to `next`,
+    /// returning its output, or return an empty slice to drop the record.
+    fn process<'a, R: Renderer>(
+        &self,
+        buf: &'a mut [u8],
+        record: &log::Record,
+        next: &R,
+    ) -> &'a [u8];
+}
+
+#[derive(Clone, Copy)]
+/// Renders records by passing them through `stage` on to `next`.
+pub struct Chain<S, R> {
+    stage: S,
+    next: R,
+}
+
+impl<S: Stage, R: Renderer> Chain<S, R> {
+    /// Put `stage` in front of `next`.
+    pub const fn new(stage: S, next: R) -> Self {
+        Self { stage, next }
+    }
+}
+
+impl<S: Stage, R: Renderer> Renderer for Chain<S, R> {
+    fn render<'a>(&self, buf: &'a mut [u8], record: &log::Record) -> &'a [u8] {
+        self.stage.process(buf, record, &self.next)
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Drops records for which the predicate returns `false`.
+pub struct Filter(pub fn(&log::Record) -> bool);
+
+impl Stage for Filter {
+    fn process<'a, R: Renderer>(
+        &self,
+        buf: &'a mut [u8],
+        record: &log::Record,
+        next: &R,
+    ) -> &'a [u8] {
+        if (self.0)(record) {
+            next.render(buf, record)
+        } else {
+            &buf[..0]
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Annotates records by writing a prefix in front of their arguments.
+///
+/// Useful to add task IDs, timestamps, or similar.
+pub struct Prefix(pub fn(&mut fmt::Formatter, &log::Record) -> fmt::Result);
+
+struct Annotation<'a, 'r> {
+    prefix: &'a Prefix,
+    record: &'a log::Record<'r>,
+}
+
+impl fmt::Display for Annotation<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.prefix.0)(f, self.record)?;
+        fmt::Display::fmt(self.record.args(), f)
+    }
+}
+
+impl Stage for Prefix {
+    fn process<'a, R: Renderer>(
+        &self,
+        buf: &'a mut [u8],
+        record: &log::Record,
+        next: &R,
+    ) -> &'a [u8] {
+        let annotation = Annotation {
+            prefix: self,
+            record,
+        };
+        next.render(buf, &with_args(record, format_args!("{}", annotation)))
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Replaces level and target of records, e.g. to demote noisy targets or group them.
+///
+/// ```
+/// use delog::render::{chain::Rewrite, Chain, RipgrepRenderer};
+/// use delog::Renderer;
+///
+/// static RENDERER: Chain<Rewrite, RipgrepRenderer> = Chain::new(
+///     Rewrite(|record| match record.target() {
+///         "chatty" => (log::Level::Trace, "app"),
+///         target => (record.level(), target),
+///     }),
+///     RipgrepRenderer::new(),
+/// );
+///
+/// let mut buf = [0u8; 64];
+/// let record = log::Record::builder()
+///     .args(format_args!("hello"))
+///     .target("chatty")
+///     .build();
+/// assert_eq!(RENDERER.render(&mut buf, &record), b"TRACE|app: hello\n");
+/// ```
+pub struct Rewrite(pub for<'a> fn(&log::Record<'a>) -> (log::Level, &'a str));
+
+impl Stage for Rewrite {
+    fn process<'a, R: Renderer>(
+        &self,
+        buf: &'a mut [u8],
+        record: &log::Record,
+        next: &R,
+    ) -> &'a [u8] {
+        let (level, target) = (self.0)(record);
+        next.render(
+            buf,
+            &copy(record, *record.args())
+                .level(level)
+                .target(target)
+                .build(),
+        )
+    }
+}
+
+/// Copy of `record` with replaced arguments, for stages that rewrite records.
+pub fn with_args<'a>(record: &'a log::Record, args: fmt::Arguments<'a>) -> log::Record<'a> {
+    copy(record, args).build()
+}
+
+fn copy<'a>(record: &'a log::Record, args: fmt::Arguments<'a>) -> log::RecordBuilder<'a> {
+    let mut builder = log::Record::builder();
+    builder
+        .args(args)
+        .level(record.level())
+        .target(record.target())
+        .module_path(record.module_path())
+        .file(record.file())
+        .line(record.line());
+    #[cfg(feature = "kv")]
+    builder.key_values(record.key_values());
+    builder
+}
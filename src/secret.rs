@@ -0,0 +1,260 @@
+//! Redaction of sensitive values in logs.
+//!
+//! Wrapping key material and similar in [`Secret`] keeps diagnostic logging calls safe to leave
+//! in: unless the `reveal-secrets` feature is active, `Display` and `Debug` only show a
+//! placeholder, optionally with a short fingerprint to tell values apart.
+//!
+//! As Cargo unifies features, `reveal-secrets` should only ever be enabled by the final
+//! binary, for development builds; libraries must not enable it.
+//!
+//! ```
+//! use delog::{hex_secret, Secret};
+//!
+//! let pin = Secret::new(1234);
+//! let key = [0xA1u8, 0xB2, 0xC3];
+//! if cfg!(feature = "reveal-secrets") {
+//!     assert_eq!(format!("{}", pin), "1234");
+//!     assert_eq!(format!("{:?}", hex_secret!(&key)), "A1 B2 C3");
+//! } else {
+//!     assert_eq!(format!("{}", pin), "<redacted>");
+//!     assert_eq!(format!("{:?}", hex_secret!(&key)), "<redacted>");
+//! }
+//! ```
+
+use core::{fmt, hash};
+
+/// Wrapper redacting its value when formatted, unless the `reveal-secrets` feature is active.
+///
+/// It is deliberately neither `Copy`, to keep copies of key material explicit, nor
+/// `PartialEq`, as the comparison would not run in constant time. Compare the exposed
+/// values with a constant-time comparison where this matters.
+#[derive(Clone)]
+pub struct Secret<T> {
+    value: T,
+    fingerprint: Option<u32>,
+}
+
+impl<T> Secret<T> {
+    /// Wrap `value`, formatting as `<redacted>`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            fingerprint: None,
+        }
+    }
+
+    /// Wrap `value`, formatting as `<redacted:...>` with a fingerprint of the value,
+    /// keyed with `key`.
+    ///
+    /// The fingerprint is 32 bits of SipHash-2-4 over the value's `Hash` implementation,
+    /// which may differ between platforms.
+    /// Using a secret key (say, a device-specific one) prevents guessing low-entropy values
+    /// from their fingerprints.
+    ///
+    /// ```
+    /// use delog::Secret;
+    ///
+    /// let key = [7u8; 16];
+    /// let formatted = format!("{}", Secret::fingerprinted(1234, &key));
+    /// # if !cfg!(feature = "reveal-secrets") {
+    /// assert!(formatted.starts_with("<redacted:") && formatted.len() == 19);
+    /// assert_eq!(formatted, format!("{}", Secret::fingerprinted(1234, &key)));
+    /// assert_ne!(formatted, format!("{}", Secret::fingerprinted(1235, &key)));
+    /// # }
+    /// ```
+    pub fn fingerprinted(value: T, key: &[u8; 16]) -> Self
+    where
+        T: hash::Hash,
+    {
+        let fingerprint = fingerprint(&value, key);
+        Self {
+            value,
+            fingerprint: Some(fingerprint),
+        }
+    }
+
+    /// Set the fingerprint to the keyed fingerprint of `bytes`, for instance the data
+    /// underlying a formatting newtype. This is what `hex_secret!` uses.
+    ///
+    /// Unlike fingerprints via `Hash`, these do not depend on the platform.
+    pub fn fingerprint_bytes(mut self, bytes: &[u8], key: &[u8; 16]) -> Self {
+        let mut hasher = SipHasher::new(key);
+        hash::Hasher::write(&mut hasher, bytes);
+        self.fingerprint = Some(hash::Hasher::finish(&hasher) as u32);
+        self
+    }
+
+    /// Access the wrapped value.
+    pub fn expose(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwrap the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn redacted(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fingerprint {
+            Some(fingerprint) => write!(f, "<redacted:{:08x}>", fingerprint),
+            None => f.write_str("<redacted>"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "reveal-secrets") {
+            fmt::Debug::fmt(&self.value, f)
+        } else {
+            self.redacted(f)
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "reveal-secrets") {
+            fmt::Display::fmt(&self.value, f)
+        } else {
+            self.redacted(f)
+        }
+    }
+}
+
+#[macro_export]
+/// Format byte arrays and slices like `hex_str!`, wrapped in a `Secret`.
+///
+/// Optionally, a key for a fingerprint of the bytes can be passed.
+///
+/// ```
+/// use delog::hex_secret;
+/// let key = [0u8; 16];
+/// let secret = [1u8, 2, 3, 4];
+/// # if !cfg!(feature = "reveal-secrets") {
+/// assert_eq!(format!("{}", hex_secret!(&secret, fingerprint: &key)), "<redacted:3a427065>");
+/// # }
+/// ```
+macro_rules! hex_secret {
+    ($array:expr) => {
+        $crate::Secret::new($crate::hex_str!($array))
+    };
+    ($array:expr, fingerprint: $key:expr) => {
+        // evaluate once, so that the fingerprint is of the wrapped bytes
+        match $array {
+            array => $crate::Secret::new($crate::hex_str!(array))
+                .fingerprint_bytes(::core::convert::AsRef::<[u8]>::as_ref(array), $key),
+        }
+    };
+}
+
+fn fingerprint<H: hash::Hash + ?Sized>(value: &H, key: &[u8; 16]) -> u32 {
+    let mut hasher = SipHasher::new(key);
+    value.hash(&mut hasher);
+    hash::Hasher::finish(&hasher) as u32
+}
+
+/// SipHash-2-4, as `core::hash::SipHasher` is deprecated.
+struct SipHasher {
+    v: [u64; 4],
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+impl SipHasher {
+    fn new(key: &[u8; 16]) -> Self {
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&key[..8]);
+        k1.copy_from_slice(&key[8..]);
+        let (k0, k1) = (u64::from_le_bytes(k0), u64::from_le_bytes(k1));
+        Self {
+            v: [
+                k0 ^ 0x736f6d6570736575,
+                k1 ^ 0x646f72616e646f6d,
+                k0 ^ 0x6c7967656e657261,
+                k1 ^ 0x7465646279746573,
+            ],
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    fn round(v: &mut [u64; 4]) {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    fn compress(v: &mut [u64; 4], m: u64) {
+        v[3] ^= m;
+        Self::round(v);
+        Self::round(v);
+        v[0] ^= m;
+    }
+}
+
+impl hash::Hasher for SipHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length += bytes.len();
+        for &byte in bytes {
+            self.tail |= (byte as u64) << (8 * self.ntail);
+            self.ntail += 1;
+            if self.ntail == 8 {
+                Self::compress(&mut self.v, self.tail);
+                self.tail = 0;
+                self.ntail = 0;
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut v = self.v;
+        Self::compress(&mut v, self.tail | ((self.length as u64) << 56));
+        v[2] ^= 0xff;
+        for _ in 0..4 {
+            Self::round(&mut v);
+        }
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::hash::Hasher;
+
+    #[test]
+    fn test_siphash() {
+        // test vector from the SipHash paper
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let message: [u8; 15] = core::array::from_fn(|i| i as u8);
+        let mut hasher = super::SipHasher::new(&key);
+        hasher.write(&message[..7]);
+        hasher.write(&message[7..]);
+        assert_eq!(hasher.finish(), 0xa129ca6149be45e5);
+    }
+
+    #[test]
+    fn test_hex_secret_evaluates_once() {
+        let key = [0u8; 16];
+        let bytes = [[1u8, 2], [3, 4]];
+        let mut calls = 0;
+        let mut next = || {
+            calls += 1;
+            &bytes[calls - 1]
+        };
+        let secret = hex_secret!(next(), fingerprint: &key);
+        let expected = hex_secret!(&bytes[0], fingerprint: &key);
+        assert_eq!(format!("{}", secret), format!("{}", expected));
+        assert_eq!(calls, 1);
+    }
+}
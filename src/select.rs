@@ -0,0 +1,152 @@
+//! Compile-time selection of levels for modules, beyond the `log-*` features.
+//!
+//! Everything here is `const`, so that the generated macros compile out unselected calls.
+
+/// Whether `path` is `module`, or inside it.
+const fn is_within(path: &[u8], module: &[u8]) -> bool {
+    if module.is_empty() || module.len() > path.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < module.len() {
+        if path[i] != module[i] {
+            return false;
+        }
+        i += 1;
+    }
+    module.len() == path.len() || path[module.len()] == b':'
+}
+
+const fn trim(s: &[u8]) -> &[u8] {
+    let mut s = s;
+    while let [b' ', rest @ ..] = s {
+        s = rest;
+    }
+    while let [rest @ .., b' '] = s {
+        s = rest;
+    }
+    s
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && is_within(a, b)
+}
+
+const fn parse_level(level: &[u8]) -> Option<log::Level> {
+    Some(if eq(level, b"error") {
+        log::Level::Error
+    } else if eq(level, b"warn") {
+        log::Level::Warn
+    } else if eq(level, b"info") {
+        log::Level::Info
+    } else if eq(level, b"debug") {
+        log::Level::Debug
+    } else if eq(level, b"trace") {
+        log::Level::Trace
+    } else {
+        return None;
+    })
+}
+
+/// Whether the module at `module_path` is, or is inside, one of the crate-relative `modules`
+/// selected for `level` or a less severe level.
+pub const fn module_enabled(
+    level: log::Level,
+    module_path: &str,
+    modules: &[(log::Level, &str)],
+) -> bool {
+    // strip the crate name
+    let mut path = module_path.as_bytes();
+    while let [c, rest @ ..] = path {
+        path = rest;
+        if *c == b':' {
+            break;
+        }
+    }
+    if let [b':', rest @ ..] = path {
+        path = rest;
+    }
+
+    let mut i = 0;
+    while i < modules.len() {
+        let (max, module) = modules[i];
+        if level as usize <= max as usize && is_within(path, module.as_bytes()) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Whether the module at `module_path` is selected for `level` in `levels`,
+/// the value of the `DELOG_LEVELS` environment variable at compile time.
+///
+/// This is a comma-separated list of `path=level` entries, where the paths are crate names
+/// or module paths, like `lib_a=info,lib_b::storage=trace`.
+pub const fn env_enabled(level: log::Level, module_path: &str, levels: Option<&str>) -> bool {
+    let mut levels = match levels {
+        Some(levels) => levels.as_bytes(),
+        None => return false,
+    };
+    let path = module_path.as_bytes();
+    while !levels.is_empty() {
+        // split off the next entry
+        let mut len = 0;
+        while len < levels.len() && levels[len] != b',' {
+            len += 1;
+        }
+        let (entry, rest) = levels.split_at(len);
+        levels = match rest {
+            [_, rest @ ..] => rest,
+            [] => rest,
+        };
+
+        let mut equals = 0;
+        while equals < entry.len() && entry[equals] != b'=' {
+            equals += 1;
+        }
+        if equals == entry.len() {
+            continue;
+        }
+        let (module, max) = entry.split_at(equals);
+        let (_, max) = max.split_at(1);
+        if let Some(max) = parse_level(trim(max)) {
+            if level as usize <= max as usize && is_within(path, trim(module)) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level::*;
+
+    #[test]
+    fn test_module_enabled() {
+        let modules = [(Debug, "storage"), (Trace, "net::tcp")];
+        assert!(module_enabled(Debug, "lib::storage", &modules));
+        assert!(module_enabled(Error, "lib::storage::journal", &modules));
+        assert!(!module_enabled(Trace, "lib::storage", &modules));
+        assert!(!module_enabled(Debug, "lib::storage2", &modules));
+        assert!(module_enabled(Trace, "lib::net::tcp", &modules));
+        assert!(!module_enabled(Trace, "lib::net", &modules));
+        assert!(!module_enabled(Error, "lib", &modules));
+        assert!(!module_enabled(Error, "lib::storage", &[]));
+    }
+
+    #[test]
+    fn test_env_enabled() {
+        let levels = Some("lib_a=info, lib_b::storage = trace,,bogus,lib_c=loud");
+        assert!(env_enabled(Info, "lib_a", levels));
+        assert!(env_enabled(Warn, "lib_a::net", levels));
+        assert!(!env_enabled(Debug, "lib_a", levels));
+        assert!(!env_enabled(Info, "lib_ab", levels));
+        assert!(env_enabled(Trace, "lib_b::storage::journal", levels));
+        assert!(!env_enabled(Error, "lib_b", levels));
+        assert!(!env_enabled(Error, "lib_c", levels));
+        assert!(!env_enabled(Error, "lib_a", None));
+    }
+}
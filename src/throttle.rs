@@ -0,0 +1,223 @@
+//! Per-call-site state of the log-once and rate-limited macros.
+//!
+//! The macros generated by `generate_macros!` include variants such as `info_once!`,
+//! `warn_every_n!(n, ...)` and `debug_throttled!(period, ...)`, each of which keeps one of
+//! these in a static at its call site. When a rate-limited call site logs again, the number of
+//! suppressed calls is appended to the message, as in `polling (41 suppressed)`.
+//!
+//! Like the other generated macros, they take an optional target first:
+//!
+//! ```
+//! use std::sync::Mutex;
+//!
+//! delog::generate_macros!(warn: ["poll"]);
+//!
+//! mod poll {
+//!     pub fn run() {
+//!         for i in 0..5 {
+//!             warn_every_n!(target: "!", 2, "poll {}", i);
+//!             warn_once!(target: "!", "first poll {}", i);
+//!         }
+//!     }
+//! }
+//!
+//! static LOGS: Mutex<String> = Mutex::new(String::new());
+//!
+//! #[derive(Debug)]
+//! struct Flusher {}
+//! impl delog::Flusher for Flusher {
+//!     fn flush(&self, logs: &str) {
+//!         LOGS.lock().unwrap().push_str(logs);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     delog::delog!(Delogger, 256, Flusher, renderer: delog::render::DefaultRenderer);
+//!     static FLUSHER: Flusher = Flusher {};
+//!     Delogger::init(delog::LevelFilter::Trace, &FLUSHER, delog::render::default()).ok();
+//!
+//!     // logged immediately, nothing is deferred
+//!     poll::run();
+//!     assert_eq!(
+//!         *LOGS.lock().unwrap(),
+//!         "poll 0\nfirst poll 0\npoll 2 (1 suppressed)\npoll 4 (1 suppressed)\n",
+//!     );
+//! }
+//! ```
+//!
+//! Time-based throttling uses the clock set via `delog::set_clock`, in its units.
+//! Without a clock, throttled calls are not suppressed.
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Lets only the first call pass.
+pub struct Once {
+    done: AtomicBool,
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Once {
+    /// New state, with no call yet.
+    pub const fn new() -> Self {
+        Self {
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this is the first call.
+    pub fn first(&self) -> bool {
+        !self.done.swap(true, Ordering::Relaxed)
+    }
+}
+
+/// Lets every `n`-th call pass, starting with the first.
+pub struct EveryN {
+    count: AtomicUsize,
+}
+
+impl Default for EveryN {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EveryN {
+    /// New state, with no call yet.
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a call, returning the number of suppressed calls since the last passed one,
+    /// if this call passes.
+    ///
+    /// ```
+    /// let every_3 = delog::throttle::EveryN::new();
+    /// let ticks: Vec<_> = (0..5).map(|_| every_3.tick(3)).collect();
+    /// assert_eq!(ticks, [Some(0), None, None, Some(2), None]);
+    /// ```
+    pub fn tick(&self, n: usize) -> Option<usize> {
+        let n = n.max(1);
+        let count = self.count.fetch_add(1, Ordering::Relaxed);
+        match count % n {
+            0 if count == 0 => Some(0),
+            0 => Some(n - 1),
+            _ => None,
+        }
+    }
+}
+
+/// Lets calls pass at most once per period of the clock.
+pub struct Throttle {
+    started: AtomicBool,
+    // truncated, as not all targets have 64 bit atomics
+    last: AtomicUsize,
+    suppressed: AtomicUsize,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Throttle {
+    /// New state, with no call yet.
+    pub const fn new() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+            last: AtomicUsize::new(0),
+            suppressed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a call, returning the number of suppressed calls since the last passed one,
+    /// if this call passes.
+    pub fn tick(&self, period: u64) -> Option<usize> {
+        self.tick_at(crate::now(), period)
+    }
+
+    fn tick_at(&self, now: Option<u64>, period: u64) -> Option<usize> {
+        let now = match now {
+            Some(now) => now as usize,
+            None => return Some(self.suppressed.swap(0, Ordering::Relaxed)),
+        };
+        if !self.started.swap(true, Ordering::Relaxed) {
+            self.last.store(now, Ordering::Relaxed);
+            return Some(0);
+        }
+
+        let period = period.min(usize::MAX as u64) as usize;
+        let last = self.last.load(Ordering::Relaxed);
+        if now.wrapping_sub(last) >= period
+            && self
+                .last
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(self.suppressed.swap(0, Ordering::Relaxed))
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_once() {
+        let once = Once::new();
+        assert!(once.first());
+        assert!(!once.first());
+        assert!(!once.first());
+    }
+
+    #[test]
+    fn test_throttle() {
+        let throttle = Throttle::new();
+        let ticks: Vec<_> = [100, 105, 109, 110, 111, 125, 140]
+            .iter()
+            .map(|&now| throttle.tick_at(Some(now), 10))
+            .collect();
+        assert_eq!(
+            ticks,
+            [Some(0), None, None, Some(2), None, Some(1), Some(0)]
+        );
+
+        // the clock may wrap around
+        let throttle = Throttle::new();
+        assert_eq!(throttle.tick_at(Some(u64::MAX - 5), 10), Some(0));
+        assert_eq!(throttle.tick_at(Some(u64::MAX), 10), None);
+        assert_eq!(throttle.tick_at(Some(4), 10), Some(1));
+    }
+
+    #[test]
+    fn test_throttle_without_clock() {
+        let throttle = Throttle::new();
+        assert_eq!(throttle.tick_at(Some(0), 10), Some(0));
+        assert_eq!(throttle.tick_at(Some(1), 10), None);
+        assert_eq!(throttle.tick_at(Some(2), 10), None);
+        // nothing is suppressed without a clock, reporting what was before
+        assert_eq!(throttle.tick_at(None, 10), Some(2));
+        assert_eq!(throttle.tick_at(None, 10), Some(0));
+        // and the window continues once the clock is set
+        assert_eq!(throttle.tick_at(Some(3), 10), None);
+        assert_eq!(throttle.tick_at(Some(10), 10), Some(1));
+    }
+
+    #[test]
+    fn test_tick() {
+        // no clock is set in the unit tests
+        let throttle = Throttle::new();
+        assert!((0..3).all(|_| throttle.tick(u64::MAX) == Some(0)));
+    }
+}
@@ -0,0 +1,157 @@
+//! Timing of scopes, for latency debugging.
+//!
+//! The macros generated by `generate_macros!` include `time_scope!("name")`, which keeps one of
+//! these guards until the end of the enclosing scope. When dropped, it logs the elapsed time
+//! through the deferred buffer, as in `isr (elapsed: 42)`, at debug level unless another
+//! level is given, as in `time_scope!(delog::Level::Trace, "isr")`.
+//!
+//! Times are read from the clock set via `delog::set_clock`, in its units. The level must be
+//! a constant: if it is compiled out, the guard is `()`, so nothing remains of the macro.
+//! If the level is compiled in but not logged, the clock is not read and nothing is logged.
+//!
+//! ```
+//! use std::sync::Mutex;
+//!
+//! delog::generate_macros!(debug: ["isr"]);
+//!
+//! mod isr {
+//!     pub fn handle() {
+//!         time_scope!("handle");
+//!         {
+//!             time_scope!(delog::Level::Debug, "inner");
+//!         }
+//!         time_scope!(delog::Level::Trace, "compiled out");
+//!     }
+//! }
+//!
+//! static LOGS: Mutex<String> = Mutex::new(String::new());
+//!
+//! #[derive(Debug)]
+//! struct Flusher {}
+//! impl delog::Flusher for Flusher {
+//!     fn flush(&self, logs: &str) {
+//!         LOGS.lock().unwrap().push_str(logs);
+//!     }
+//! }
+//!
+//! fn ticks() -> u64 {
+//!     static TICKS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+//!     TICKS.fetch_add(10, std::sync::atomic::Ordering::Relaxed)
+//! }
+//!
+//! fn main() {
+//!     delog::delog!(Delogger, 256, Flusher, renderer: delog::render::DefaultRenderer);
+//!     static FLUSHER: Flusher = Flusher {};
+//!     Delogger::init(delog::LevelFilter::Trace, &FLUSHER, delog::render::default()).ok();
+//!     delog::set_clock(ticks);
+//!
+//!     isr::handle();
+//!     Delogger::flush();
+//!     assert_eq!(*LOGS.lock().unwrap(), "inner (elapsed: 10)\nhandle (elapsed: 30)\n");
+//! }
+//! ```
+
+use log::Level;
+
+/// Logs the time elapsed since its creation when dropped.
+///
+/// Use the generated `time_scope!` macro to construct this.
+pub struct TimeScope {
+    level: Level,
+    name: &'static str,
+    start: u64,
+    location: &'static (&'static str, &'static str, &'static str, u32),
+}
+
+impl TimeScope {
+    /// Start timing, if a clock is set.
+    ///
+    /// The location consists of target, module path, file and line, as for the logging macros.
+    pub fn start(
+        level: Level,
+        name: &'static str,
+        location: &'static (&'static str, &'static str, &'static str, u32),
+    ) -> Option<Self> {
+        Some(Self {
+            level,
+            name,
+            start: crate::now()?,
+            location,
+        })
+    }
+}
+
+impl Drop for TimeScope {
+    fn drop(&mut self) {
+        if let Some(now) = crate::now() {
+            let elapsed = now.wrapping_sub(self.start);
+            crate::__private_api_try_log(
+                format_args!("{} (elapsed: {})", self.name, elapsed),
+                self.level,
+                self.location,
+            )
+            .ok();
+        }
+    }
+}
+
+/// Selects the guard of `time_scope!` by whether its level is compiled in.
+pub struct Gate<const ENABLED: bool>;
+
+/// Starts the guard selected by `Gate`.
+pub trait Start {
+    /// The guard, `()` for compiled out levels and `Option<TimeScope>` otherwise.
+    type Guard;
+
+    /// Start the guard, see `TimeScope::start`.
+    fn start(
+        level: Level,
+        name: &'static str,
+        location: &'static (&'static str, &'static str, &'static str, u32),
+    ) -> Self::Guard;
+}
+
+impl Start for Gate<false> {
+    type Guard = ();
+
+    #[inline(always)]
+    fn start(
+        _: Level,
+        _: &'static str,
+        _: &'static (&'static str, &'static str, &'static str, u32),
+    ) {
+    }
+}
+
+impl Start for Gate<true> {
+    type Guard = Option<TimeScope>;
+
+    /// Start timing if the level is logged, according to the filters of `log`.
+    fn start(
+        level: Level,
+        name: &'static str,
+        location: &'static (&'static str, &'static str, &'static str, u32),
+    ) -> Option<TimeScope> {
+        if level <= log::STATIC_MAX_LEVEL && level <= log::max_level() {
+            TimeScope::start(level, name, location)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Gate, Start};
+
+    static LOCATION: (&str, &str, &str, u32) = ("delog", "delog", "src/timing.rs", 0);
+
+    #[test]
+    fn test_gate() {
+        // nothing is left of compiled out scopes
+        #[allow(clippy::let_unit_value)]
+        let () = <Gate<false> as Start>::start(log::Level::Trace, "scope", &LOCATION);
+        // no clock is set in the unit tests
+        assert!(<Gate<true> as Start>::start(log::Level::Error, "scope", &LOCATION).is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! Bounded formatting of arbitrarily large values.
+//!
+//! The render buffer is shared by the whole log message, so formatting one large value,
+//! such as `trace!("{:?}", &big_struct)`, can crowd out everything after it. Wrapping it in
+//! `trunc!` stops the output after a given number of characters, and optionally elides
+//! nested structure below a given depth.
+//!
+//! ```
+//! use delog::trunc;
+//!
+//! #[derive(Debug)]
+//! struct Inner { values: [u8; 4] }
+//! #[derive(Debug)]
+//! struct Outer { name: &'static str, inner: Inner }
+//!
+//! let outer = Outer { name: "outer", inner: Inner { values: [1, 2, 3, 4] } };
+//! assert_eq!(format!("{:?}", trunc!(&outer, 20)), "Outer { name: \"outer..");
+//! assert_eq!(format!("{}", trunc!("hello, world", 5)), "hello..");
+//! assert_eq!(
+//!     format!("{:?}", trunc!(&outer, 100, depth: 2)),
+//!     "Outer { name: \"outer\", inner: Inner { values: [..] } }",
+//! );
+//! assert_eq!(
+//!     format!("{:#?}", trunc!(&outer, 100, depth: 1)),
+//!     "Outer {\n    name: \"outer\",\n    inner: Inner {..},\n}",
+//! );
+//! ```
+
+use core::fmt::{self, Write};
+
+/// Wrapper newtype, limiting the formatted length (and optionally the nesting depth)
+/// of its value's `Display` or `Debug` output.
+///
+/// The alternate flag, width and precision are passed on to the value, other flags are not.
+///
+/// Use the `trunc!` macro to construct this.
+pub struct Trunc<'a, T: ?Sized> {
+    /// The value to be formatted.
+    pub value: &'a T,
+    max_chars: usize,
+    max_depth: usize,
+}
+
+impl<'a, T: ?Sized> Trunc<'a, T> {
+    /// Wrap `value`, eliding output after `max_chars` characters.
+    pub const fn new(value: &'a T, max_chars: usize) -> Self {
+        Self {
+            value,
+            max_chars,
+            max_depth: usize::MAX,
+        }
+    }
+
+    /// Elide the contents of brackets and braces nested more than `max_depth` levels.
+    ///
+    /// Strings and characters are recognized and left alone, so this works with
+    /// derived `Debug` implementations, pretty-printed or not.
+    pub const fn depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+#[macro_export]
+/// Format a value with `Display` or `Debug`, stopping with `..` after a maximum
+/// number of characters.
+///
+/// A maximum nesting depth can be passed additionally, see `Trunc::depth`.
+///
+/// ```
+/// use delog::trunc;
+/// let data = [1u32; 100];
+/// assert_eq!(format!("{:?}", trunc!(&data, 12)), "[1, 1, 1, 1,..");
+/// ```
+macro_rules! trunc {
+    ($value:expr, $max_chars:expr) => {
+        $crate::trunc::Trunc::new($value, $max_chars)
+    };
+    ($value:expr, $max_chars:expr, depth: $max_depth:expr) => {
+        $crate::trunc::Trunc::new($value, $max_chars).depth($max_depth)
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    None,
+    String,
+    Char,
+}
+
+/// Passes on characters until the limits are hit, recording whether it did.
+struct Limited<'f, 'a> {
+    f: &'f mut fmt::Formatter<'a>,
+    remaining: usize,
+    truncated: bool,
+    max_depth: usize,
+    depth: usize,
+    quote: Quote,
+    escaped: bool,
+}
+
+impl Limited<'_, '_> {
+    fn emit(&mut self, c: char) -> fmt::Result {
+        if self.remaining == 0 {
+            self.truncated = true;
+            return Err(fmt::Error);
+        }
+        self.remaining -= 1;
+        self.f.write_char(c)
+    }
+
+    /// Tracks nesting outside of quotes, returning the depth before and after `c`.
+    fn nest(&mut self, c: char) -> (usize, usize) {
+        let before = self.depth;
+        if self.escaped {
+            self.escaped = false;
+            return (before, before);
+        }
+        match (self.quote, c) {
+            (Quote::String | Quote::Char, '\\') => self.escaped = true,
+            (Quote::String, '"') | (Quote::Char, '\'') => self.quote = Quote::None,
+            (Quote::None, '"') => self.quote = Quote::String,
+            (Quote::None, '\'') => self.quote = Quote::Char,
+            (Quote::None, '(' | '[' | '{') => self.depth += 1,
+            (Quote::None, ')' | ']' | '}') => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        (before, self.depth)
+    }
+}
+
+impl Write for Limited<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let (before, after) = self.nest(c);
+            if before <= self.max_depth && after <= self.max_depth {
+                self.emit(c)?;
+            } else if after > before && before == self.max_depth {
+                // opening the first elided level
+                self.emit(c)?;
+                self.emit('.')?;
+                self.emit('.')?;
+            } else if after < before && after == self.max_depth {
+                self.emit(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: ?Sized> Trunc<'_, T> {
+    fn limited(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        format: impl FnOnce(&mut Limited) -> fmt::Result,
+    ) -> fmt::Result {
+        let mut limited = Limited {
+            f,
+            remaining: self.max_chars,
+            truncated: false,
+            max_depth: self.max_depth,
+            depth: 0,
+            quote: Quote::None,
+            escaped: false,
+        };
+        match format(&mut limited) {
+            Err(_) if limited.truncated => limited.f.write_str(".."),
+            result => result,
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for Trunc<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.value;
+        match (f.alternate(), f.width(), f.precision()) {
+            (false, None, None) => self.limited(f, |l| write!(l, "{:?}", value)),
+            (false, Some(w), None) => self.limited(f, |l| write!(l, "{:w$?}", value)),
+            (false, None, Some(p)) => self.limited(f, |l| write!(l, "{:.p$?}", value)),
+            (false, Some(w), Some(p)) => self.limited(f, |l| write!(l, "{:w$.p$?}", value)),
+            (true, None, None) => self.limited(f, |l| write!(l, "{:#?}", value)),
+            (true, Some(w), None) => self.limited(f, |l| write!(l, "{:#w$?}", value)),
+            (true, None, Some(p)) => self.limited(f, |l| write!(l, "{:#.p$?}", value)),
+            (true, Some(w), Some(p)) => self.limited(f, |l| write!(l, "{:#w$.p$?}", value)),
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for Trunc<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.value;
+        match (f.width(), f.precision()) {
+            (None, None) => self.limited(f, |l| write!(l, "{}", value)),
+            (Some(w), None) => self.limited(f, |l| write!(l, "{:w$}", value)),
+            (None, Some(p)) => self.limited(f, |l| write!(l, "{:.p$}", value)),
+            (Some(w), Some(p)) => self.limited(f, |l| write!(l, "{:w$.p$}", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Trunc;
+
+    #[test]
+    fn test_quotes() {
+        // brackets in strings and characters do not count
+        let value = ("[", ']', "\"{", '\'', "\\");
+        assert_eq!(
+            format!("{:?}", Trunc::new(&value, 100).depth(1)),
+            r#"("[", ']', "\"{", '\'', "\\")"#
+        );
+        assert_eq!(format!("{:?}", Trunc::new(&value, 100).depth(0)), "(..)");
+
+        let value = [("]", [1]), ("[", [2])];
+        assert_eq!(
+            format!("{:?}", Trunc::new(&value, 100).depth(2)),
+            r#"[("]", [..]), ("[", [..])]"#
+        );
+        // the elision counts towards the limit
+        assert_eq!(
+            format!("{:?}", Trunc::new(&value, 12).depth(2)),
+            r#"[("]", [..]).."#
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(format!("{:.2}", Trunc::new(&1.23456, 10)), "1.23");
+        assert_eq!(format!("{:8.2?}", Trunc::new(&1.23456, 10)), "    1.23");
+        assert_eq!(
+            format!("{:#5?}", Trunc::new(&[255u8], 100)),
+            "[\n      255,\n]"
+        );
+        // padding counts towards the limit
+        assert_eq!(format!("{:6}", Trunc::new("ab", 4)), "ab  ..");
+        assert_eq!(format!("{:.3}", Trunc::new("abcdef", 4)), "abc");
+    }
+
+    #[test]
+    fn test_multibyte() {
+        // characters are counted, never split
+        assert_eq!(format!("{}", Trunc::new("héllo wörld", 7)), "héllo w..");
+        assert_eq!(format!("{}", Trunc::new("日本語", 2)), "日本..");
+        assert_eq!(format!("{:?}", Trunc::new("🦀🦀", 2)), "\"🦀..");
+        assert_eq!(format!("{}", Trunc::new("日本語", 3)), "日本語");
+    }
+}